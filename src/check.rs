@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::factory::{build_interfaces, build_notifiers, build_providers};
+use crate::setting::Setting;
+use crate::{IpType, Shutdown};
+
+/// Print the problems, returns whether there is none
+fn report(problems: &[String]) -> bool {
+    for problem in problems {
+        println!("error: {problem}");
+    }
+    if !problems.is_empty() {
+        println!("{} problem(s) found", problems.len());
+    }
+    problems.is_empty()
+}
+
+/// Validate the config and print the problems, returns whether the config is fine
+///
+/// With `live`, everything is built like running and the records of each provider are queried, so the secret
+/// references, templates and credentials are checked too.
+pub(crate) async fn check(shutdown: Arc<Shutdown>, setting: Setting, live: bool) -> bool {
+    if !report(&setting.validate()) {
+        return false;
+    }
+
+    if live {
+        let mut problems = vec![];
+        if let Err(err) = build_interfaces(setting.interfaces.clone(), HashMap::new()).await {
+            problems.push(format!("{err:#}"));
+        }
+        if let Err(err) = build_notifiers(&setting.base, setting.notifiers.clone()).await {
+            problems.push(format!("{err:#}"));
+        }
+        match build_providers(shutdown, setting.providers.clone()).await {
+            Ok(providers) => {
+                let mut providers = providers.into_iter().collect::<Vec<_>>();
+                providers.sort_by(|(a, _), (b, _)| a.cmp(b));
+                for (name, (provider, _, _)) in providers {
+                    // both families are queried, so the providers no task uses are checked too
+                    for family in [IpType::V4, IpType::V6] {
+                        match provider.get_ips(family).await {
+                            Ok(ips) => {
+                                let ips = ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+                                println!("provider '{name}' has {family} record(s): [{ips}]");
+                            },
+                            Err(err) => {
+                                problems.push(format!("can't get {family} records of provider '{name}': {err:#}"))
+                            },
+                        }
+                    }
+                }
+            },
+            Err(err) => problems.push(format!("{err:#}")),
+        }
+        if !report(&problems) {
+            return false;
+        }
+    }
+
+    println!("config is valid");
+    true
+}
@@ -0,0 +1,41 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::factory::build_interfaces;
+use crate::setting::Setting;
+use crate::IpType;
+
+/// Print the addresses the interface gets for each family, returns whether any is got
+pub(crate) async fn detect(setting: Setting, name: &str) -> bool {
+    match run(setting, name).await {
+        Ok(found) => found,
+        Err(err) => {
+            println!("error: {err:#}");
+            false
+        },
+    }
+}
+
+async fn run(setting: Setting, name: &str) -> Result<bool> {
+    if !setting.interfaces.contains_key(name) {
+        bail!("can't find interface '{}'", name);
+    }
+    // only the interface and the members it needs are built
+    let needed = setting.interfaces_with_members([name]);
+    let interfaces = build_interfaces(needed, HashMap::new()).await?;
+    let interface = &interfaces[name];
+
+    let mut found = false;
+    for family in [IpType::V4, IpType::V6] {
+        match interface.get_ip(family).await {
+            Ok(ips) => {
+                found |= !ips.is_empty();
+                let ips = ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+                println!("{family}: [{ips}]");
+            },
+            Err(err) => println!("{family}: {err:#}"),
+        }
+    }
+    Ok(found)
+}
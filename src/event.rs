@@ -0,0 +1,287 @@
+use std::fmt::{Display, Formatter, Write};
+use std::net::IpAddr;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::notifiers::hostname;
+use crate::providers::Change;
+use crate::IpType;
+
+/// The kinds of event a notifier can subscribe to
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Changed,
+    Failed,
+    FailedRepeatedly,
+    Recovered,
+    Digest,
+}
+
+impl Display for EventKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            EventKind::Changed => "changed",
+            EventKind::Failed => "failed",
+            EventKind::FailedRepeatedly => "failed_repeatedly",
+            EventKind::Recovered => "recovered",
+            EventKind::Digest => "digest",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// Everything a notifier will be told about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// The records of a task have been changed
+    Changed(ChangeEvent),
+    /// A task failed after succeeding
+    Failed(FailureEvent),
+    /// A task keeps failing and the number of consecutive failures reached the threshold
+    FailedRepeatedly(FailureEvent),
+    /// A task succeeded after failing
+    Recovered(RecoveryEvent),
+    /// Events held back by the throttling of a notifier, sent together
+    Digest(DigestEvent),
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Changed(_) => EventKind::Changed,
+            Event::Failed(_) => EventKind::Failed,
+            Event::FailedRepeatedly(_) => EventKind::FailedRepeatedly,
+            Event::Recovered(_) => EventKind::Recovered,
+            Event::Digest(_) => EventKind::Digest,
+        }
+    }
+
+    pub fn task(&self) -> &str {
+        match self {
+            Event::Changed(event) => &event.task,
+            Event::Failed(event) | Event::FailedRepeatedly(event) => &event.task,
+            Event::Recovered(event) => &event.task,
+            Event::Digest(event) => &event.task,
+        }
+    }
+
+    pub fn timestamp(&self) -> DateTime<Local> {
+        match self {
+            Event::Changed(event) => event.timestamp,
+            Event::Failed(event) | Event::FailedRepeatedly(event) => event.timestamp,
+            Event::Recovered(event) => event.timestamp,
+            Event::Digest(event) => event.timestamp,
+        }
+    }
+
+    /// The record name, or the task name if the provider has no record name
+    pub fn target(&self) -> &str {
+        let domain = match self {
+            Event::Changed(event) => &event.domain,
+            Event::Failed(event) | Event::FailedRepeatedly(event) => &event.domain,
+            Event::Recovered(event) => &event.domain,
+            Event::Digest(_) => &None,
+        };
+        domain.as_deref().unwrap_or_else(|| self.task())
+    }
+
+    /// One line summary, used as the subject or title of the notification
+    pub fn title(&self) -> String {
+        match self {
+            Event::Changed(event) => format!("{} {} has been changed", self.target(), event.family),
+            Event::Failed(_) => format!("{} failed to update", self.target()),
+            Event::FailedRepeatedly(event) => {
+                format!("{} failed to update {} times in a row", self.target(), event.failures)
+            },
+            Event::Recovered(_) => format!("{} has been recovered", self.target()),
+            Event::Digest(event) => format!("{} events of {}", event.events.len(), self.target()),
+        }
+    }
+
+    /// The fields of the event as label and value pairs, values may have multiple lines
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = vec![("Task", self.task().to_owned())];
+        match self {
+            Event::Changed(event) => {
+                details.push(("Provider", event.provider.clone()));
+                if let Some(domain) = &event.domain {
+                    details.push(("Domain", domain.clone()));
+                }
+                details.push(("Family", event.family.to_string()));
+                details.push(("Old IP List", join_ips(&event.old_ips)));
+                details.push(("New IP List", join_ips(&event.new_ips)));
+                let changes = event.changes.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                details.push(("Changes", changes.join("\n")));
+                details.push(("Host", event.hostname.clone()));
+            },
+            Event::Failed(event) | Event::FailedRepeatedly(event) => {
+                details.push(("Provider", event.provider.clone()));
+                if let Some(domain) = &event.domain {
+                    details.push(("Domain", domain.clone()));
+                }
+                details.push(("Error", event.error.clone()));
+                details.push(("Failures", event.failures.to_string()));
+                details.push(("Failing Since", event.since.to_rfc3339()));
+                details.push(("Host", event.hostname.clone()));
+            },
+            Event::Recovered(event) => {
+                details.push(("Provider", event.provider.clone()));
+                if let Some(domain) = &event.domain {
+                    details.push(("Domain", domain.clone()));
+                }
+                details.push(("Failures", event.failures.to_string()));
+                details.push(("Failing Since", event.since.to_rfc3339()));
+                details.push(("Host", event.hostname.clone()));
+            },
+            Event::Digest(event) => {
+                let events = event
+                    .events
+                    .iter()
+                    .map(|v| format!("{} {}", v.timestamp().format("%F %T"), v.title()))
+                    .collect::<Vec<_>>();
+                details.push(("Events", events.join("\n")));
+                details.push(("Since", event.since.to_rfc3339()));
+                details.push(("Host", event.hostname.clone()));
+            },
+        }
+        details.push(("Time", self.timestamp().to_rfc3339()));
+        details
+    }
+
+    /// Multi-line plain text description of the event
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for (label, value) in self.details() {
+            if value.contains('\n') {
+                let _ = writeln!(text, "{}:", label);
+                for line in value.lines() {
+                    let _ = writeln!(text, "\t{}", line);
+                }
+            } else {
+                let _ = writeln!(text, "{}: {}", label, value);
+            }
+        }
+        text.truncate(text.trim_end().len());
+        text
+    }
+}
+
+/// The records of a task have been changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// Name of the task in the config file
+    pub task: String,
+    /// Name of the provider in the config file
+    pub provider: String,
+    /// The record name managed by the provider
+    pub domain: Option<String>,
+    pub family: IpType,
+    /// The addresses of the records before updating
+    pub old_ips: Vec<IpAddr>,
+    /// The addresses obtained from the interface, the records have been updated to them
+    pub new_ips: Vec<IpAddr>,
+    pub changes: Vec<Change>,
+    pub timestamp: DateTime<Local>,
+    /// The host running ddns-rs
+    pub hostname: String,
+}
+
+impl ChangeEvent {
+    /// The record name, or the task name if the provider has no record name
+    pub fn target(&self) -> &str {
+        self.domain.as_deref().unwrap_or(&self.task)
+    }
+
+    pub fn new(
+        task: &str,
+        provider: &str,
+        domain: Option<&str>,
+        family: IpType,
+        old_ips: Vec<IpAddr>,
+        new_ips: Vec<IpAddr>,
+        changes: Vec<Change>,
+    ) -> ChangeEvent {
+        ChangeEvent {
+            task: task.to_owned(),
+            provider: provider.to_owned(),
+            domain: domain.map(|v| v.to_owned()),
+            family,
+            old_ips,
+            new_ips,
+            changes,
+            timestamp: Local::now(),
+            hostname: hostname(),
+        }
+    }
+}
+
+/// A task failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailureEvent {
+    pub task: String,
+    pub provider: String,
+    pub domain: Option<String>,
+    /// The error of the last run
+    pub error: String,
+    /// The number of consecutive failures
+    pub failures: u32,
+    /// The time of the first failure in a row
+    pub since: DateTime<Local>,
+    pub timestamp: DateTime<Local>,
+    pub hostname: String,
+}
+
+/// A task succeeded after failing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryEvent {
+    pub task: String,
+    pub provider: String,
+    pub domain: Option<String>,
+    /// The number of consecutive failures before recovering
+    pub failures: u32,
+    /// The time of the first failure in a row
+    pub since: DateTime<Local>,
+    pub timestamp: DateTime<Local>,
+    pub hostname: String,
+}
+
+/// Events held back by the throttling of a notifier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestEvent {
+    /// The names of the tasks of the events
+    pub task: String,
+    pub events: Vec<Event>,
+    /// The time of the first event
+    pub since: DateTime<Local>,
+    pub timestamp: DateTime<Local>,
+    pub hostname: String,
+}
+
+impl DigestEvent {
+    pub fn new(events: Vec<Event>) -> DigestEvent {
+        let mut tasks: Vec<&str> = vec![];
+        for event in &events {
+            if !tasks.contains(&event.task()) {
+                tasks.push(event.task());
+            }
+        }
+        DigestEvent {
+            task: tasks.join(", "),
+            since: events.first().map(|v| v.timestamp()).unwrap_or_else(Local::now),
+            events,
+            timestamp: Local::now(),
+            hostname: hostname(),
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn join_ips(ips: &[IpAddr]) -> String {
+    if ips.is_empty() {
+        return "-".to_owned();
+    }
+    ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", ")
+}
@@ -1,165 +1,432 @@
 use std::collections::HashMap;
-use std::net::IpAddr;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::{anyhow, bail, Result};
-use toml::Value;
+use pnet::ipnetwork::IpNetwork;
+use regex::Regex;
 
 use crate::interfaces::Interface;
 use crate::notifiers::Notifier;
 use crate::providers::DynProvider;
-use crate::{interfaces, notifiers, providers, Shutdown};
+use crate::secret::Secret;
+use crate::setting::{
+    Base, CompositeStrategy, InterfaceConfig, NotifierConfig, NtfyPriority, ProviderConfig, TelegramChatId,
+};
+use crate::{interfaces, notifiers, providers, setting, Shutdown, SubscribedNotifier};
 
-macro_rules! from_args_str {
-    ($args:ident, $key:literal) => {{
-        let _hidden = $args.get($key).ok_or(anyhow!(concat!("missing ", $key, " arg")))?;
-        _hidden
-            .as_str()
-            .ok_or(anyhow!(concat!("arg ", $key, " unknown type")))?
-    }};
-}
+/// A provider with the ttl and the force flag of its records
+pub(crate) type ConfiguredProvider = (Rc<Box<dyn DynProvider>>, u32, bool);
 
-macro_rules! option_from_args_str {
-    ($args:ident, $key:literal) => {{
-        if let Some(_hidden) = $args.get($key) {
-            Some(
-                _hidden
-                    .as_str()
-                    .ok_or(anyhow!(concat!("arg ", $key, " unknown type")))?,
-            )
-        } else {
-            None
-        }
-    }};
+fn read_template(path: &str) -> Result<String> {
+    std::fs::read_to_string(path).map_err(|err| anyhow!("can't read template {}: {}", path, err))
 }
 
-macro_rules! option_from_args_bool {
-    ($args:ident, $key:literal) => {{
-        if let Some(_hidden) = $args.get($key) {
-            Some(
-                _hidden
-                    .as_bool()
-                    .ok_or(anyhow!(concat!("arg ", $key, " unknown type")))?,
-            )
-        } else {
-            None
-        }
-    }};
+/// Resolve the secret references of a credential arg
+fn resolve(arg: &str, secret: &Secret) -> Result<String> {
+    secret.resolve().map_err(|err| anyhow!("arg {}: {:#}", arg, err))
 }
 
-macro_rules! option_from_args_integer {
-    ($args:ident, $key:literal) => {{
-        if let Some(_hidden) = $args.get($key) {
-            Some(
-                _hidden
-                    .as_integer()
-                    .ok_or(anyhow!(concat!("arg ", $key, " unknown type")))?,
-            )
-        } else {
-            None
-        }
-    }};
+fn resolve_optional(arg: &str, secret: &Option<Secret>) -> Result<Option<String>> {
+    secret.as_ref().map(|v| resolve(arg, v)).transpose()
 }
 
-pub(crate) async fn create_interface<S: AsRef<str>>(
-    kind: S,
-    args: HashMap<String, Value>,
+pub(crate) async fn create_interface(
+    config: InterfaceConfig,
+    built: &HashMap<String, Rc<Box<dyn Interface>>>,
 ) -> Result<Box<dyn Interface>> {
-    let interface: Box<dyn Interface> = match kind.as_ref() {
-        "peer" => {
-            let url_v4 = from_args_str!(args, "url_v4");
-            let url_v6 = from_args_str!(args, "url_v6");
-            let ipv4_field_path = from_args_str!(args, "ipv4_field_path");
-            let ipv6_field_path = from_args_str!(args, "ipv6_field_path");
-            Box::new(interfaces::Peer::create(
-                url_v4,
-                url_v6,
-                ipv4_field_path,
-                ipv6_field_path,
-            )?)
-        },
-        "stock" => {
-            let name = from_args_str!(args, "name");
-            Box::new(interfaces::Stock::create(name)?)
+    let interface: Box<dyn Interface> = match config {
+        InterfaceConfig::Peer(config) => Box::new(interfaces::Peer::create(
+            config.url_v4,
+            config.url_v6,
+            config.ipv4_field_path,
+            config.ipv6_field_path,
+        )?),
+        InterfaceConfig::Stock(config) => {
+            let selector = match (config.name, config.name_regex, config.mac, config.default_route) {
+                (Some(name), None, None, false) => {
+                    if name.contains(['*', '?']) {
+                        interfaces::StockSelector::glob(&name)?
+                    } else {
+                        interfaces::StockSelector::Name(name)
+                    }
+                },
+                (None, Some(name_regex), None, false) => interfaces::StockSelector::Pattern(
+                    Regex::new(&name_regex).map_err(|err| anyhow!("regex illegal {}: {}", name_regex, err))?,
+                ),
+                (None, None, Some(mac), false) => interfaces::StockSelector::Mac(
+                    mac.parse()
+                        .map_err(|err| anyhow!("can't parse mac address '{}': {:?}", mac, err))?,
+                ),
+                (None, None, None, true) => interfaces::StockSelector::DefaultRoute,
+                _ => bail!("exactly one of name, name_regex, mac or default_route is required"),
+            };
+            let parse_networks = |networks: Vec<String>| -> Result<Vec<IpNetwork>> {
+                let mut result = vec![];
+                for network in networks {
+                    result.push(
+                        network
+                            .parse()
+                            .map_err(|err| anyhow!("can't parse network '{}': {}", network, err))?,
+                    );
+                }
+                Ok(result)
+            };
+            let filter = interfaces::StockFilter {
+                include: parse_networks(config.include)?,
+                exclude: parse_networks(config.exclude)?,
+                exclude_temporary: config.exclude_temporary,
+                exclude_deprecated: config.exclude_deprecated,
+                prefer_stable: config.prefer_stable,
+                max_addresses: config.max_addresses,
+                allow_private: config.allow_private,
+            };
+            Box::new(interfaces::Stock::create(selector, filter)?)
         },
-        _ => {
-            bail!("the kind of interface '{}' not support", kind.as_ref())
+        InterfaceConfig::Static(config) => Box::new(interfaces::Static::create(config.addresses)?),
+        InterfaceConfig::File(config) => Box::new(interfaces::File::create(config.path, config.watch)?),
+        InterfaceConfig::Composite(config) => {
+            let mut members = vec![];
+            for name in config.members {
+                let member = built
+                    .get(&name)
+                    .ok_or_else(|| anyhow!("can't find member interface define '{}'", name))?
+                    .clone();
+                members.push((name, member));
+            }
+            let strategy = match config.strategy {
+                CompositeStrategy::Fallback => interfaces::CompositeStrategy::Fallback,
+                CompositeStrategy::Union => interfaces::CompositeStrategy::Union,
+                // majority of the members by default
+                CompositeStrategy::Consensus => {
+                    interfaces::CompositeStrategy::Consensus(config.quorum.unwrap_or(members.len() / 2 + 1))
+                },
+            };
+            Box::new(interfaces::Composite::create(members, strategy)?)
         },
     };
     Ok(interface)
 }
 
-pub(crate) async fn create_notifier<S: AsRef<str>>(
-    kind: S,
-    args: HashMap<String, Value>,
-) -> Result<Option<Box<dyn Notifier>>> {
-    let notifier: Option<Box<dyn Notifier>> = match kind.as_ref() {
-        "email" => {
-            let smtp_username = from_args_str!(args, "smtp_username");
-            let smtp_password = from_args_str!(args, "smtp_password");
-            let smtp_host = from_args_str!(args, "smtp_host");
-            let smtp_port = option_from_args_integer!(args, "smtp_port");
-            let smtp_starttls = option_from_args_bool!(args, "smtp_starttls").unwrap_or(true);
-            let to = from_args_str!(args, "to");
-            let subject = option_from_args_str!(args, "subject");
-            let from = option_from_args_str!(args, "from");
+pub(crate) async fn create_notifier(config: NotifierConfig) -> Result<Option<Box<dyn Notifier>>> {
+    let notifier: Option<Box<dyn Notifier>> = match config {
+        NotifierConfig::Email(config) => {
+            // smtp_starttls is kept for the configs written before smtp_tls
+            let smtp_tls = match (config.smtp_tls, config.smtp_starttls) {
+                (Some(smtp_tls), _) => smtp_tls,
+                (None, Some(false)) => notifiers::EmailTls::Implicit,
+                (None, _) => notifiers::EmailTls::Starttls,
+            };
+            let html_template = match &config.html_template_file {
+                Some(path) => Some(read_template(path)?),
+                None => config.html_template,
+            };
+            let text_template = match &config.text_template_file {
+                Some(path) => Some(read_template(path)?),
+                None => config.text_template,
+            };
             Some(Box::new(
                 notifiers::Email::create(
-                    smtp_username,
-                    smtp_password,
-                    smtp_host,
-                    smtp_port.map(|v| v as u16),
-                    smtp_starttls,
-                    subject,
-                    from,
-                    to,
+                    config.smtp_username,
+                    resolve_optional("smtp_password", &config.smtp_password)?,
+                    config.smtp_host,
+                    config.smtp_port,
+                    smtp_tls,
+                    config.subject,
+                    html_template,
+                    text_template,
+                    config.from,
+                    config.to.into_vec(),
+                    config.cc.into_vec(),
+                    config.bcc.into_vec(),
                 )
                 .await?,
             ))
         },
-        "webhook" => {
-            let url = from_args_str!(args, "url");
-            let authorization_header = from_args_str!(args, "authorization_header");
-            let local_address = option_from_args_str!(args, "local_address");
-            let local_address = if let Some(local_address) = local_address {
-                Some(local_address.parse::<IpAddr>()?)
-            } else {
-                None
+        NotifierConfig::Webhook(config) => {
+            let signing = resolve_optional("signing_secret", &config.signing_secret)?;
+            let signing = signing.map(|secret| notifiers::WebhookSigning {
+                secret,
+                signature_header: config.signature_header.unwrap_or_else(|| "X-DDNS-Signature".to_owned()),
+                timestamp_header: config.timestamp_header.unwrap_or_else(|| "X-DDNS-Timestamp".to_owned()),
+                delivery_id_header: config
+                    .delivery_id_header
+                    .unwrap_or_else(|| "X-DDNS-Delivery".to_owned()),
+            });
+            Some(Box::new(
+                notifiers::Webhook::create(
+                    config.url,
+                    config.method,
+                    config.headers.into_iter().collect(),
+                    config.content_type,
+                    config.body,
+                    resolve_optional("authorization_header", &config.authorization_header)?,
+                    signing,
+                    config.local_address,
+                )
+                .await?,
+            ))
+        },
+        NotifierConfig::Telegram(config) => {
+            let chat_id = match config.chat_id {
+                TelegramChatId::Id(chat_id) => chat_id.to_string(),
+                TelegramChatId::Username(chat_id) => chat_id,
             };
+            let parse_mode = config.parse_mode.unwrap_or(notifiers::TelegramParseMode::Plain);
             Some(Box::new(
-                notifiers::Webhook::create(url, authorization_header, local_address).await?,
+                notifiers::Telegram::create(
+                    resolve("bot_token", &config.bot_token)?,
+                    chat_id,
+                    config.thread_id,
+                    parse_mode,
+                    config.silent,
+                    config.api_base_url,
+                )
+                .await?,
             ))
         },
-        "empty" => None,
-        _ => {
-            bail!("the kind of notifiers '{}' not support", kind.as_ref())
+        NotifierConfig::Slack(config) => Some(Box::new(
+            notifiers::Slack::create(
+                resolve("webhook_url", &config.webhook_url)?,
+                config.channel,
+                config.username,
+                config.icon_emoji,
+            )
+            .await?,
+        )),
+        NotifierConfig::Discord(config) => Some(Box::new(
+            notifiers::Discord::create(
+                resolve("webhook_url", &config.webhook_url)?,
+                config.username,
+                config.avatar_url,
+            )
+            .await?,
+        )),
+        NotifierConfig::Teams(config) => Some(Box::new(
+            notifiers::Teams::create(resolve("webhook_url", &config.webhook_url)?).await?,
+        )),
+        NotifierConfig::Ntfy(config) => {
+            let priority = match config.priority {
+                Some(NtfyPriority::Level(priority)) => Some(priority),
+                Some(NtfyPriority::Name(priority)) => Some(notifiers::parse_ntfy_priority(&priority)?),
+                None => None,
+            };
+            let token = resolve_optional("token", &config.token)?;
+            let password = resolve_optional("password", &config.password)?;
+            let auth = match (token, config.username, password) {
+                (None, None, None) => notifiers::NtfyAuth::None,
+                (Some(token), None, None) => notifiers::NtfyAuth::Token(token),
+                (None, Some(username), Some(password)) => notifiers::NtfyAuth::Basic(username, password),
+                _ => bail!("either token or username and password is required for ntfy auth"),
+            };
+            Some(Box::new(
+                notifiers::Ntfy::create(config.server, config.topic, priority, config.tags, auth).await?,
+            ))
         },
+        NotifierConfig::Gotify(config) => Some(Box::new(
+            notifiers::Gotify::create(config.server, resolve("app_token", &config.app_token)?, config.priority).await?,
+        )),
+        NotifierConfig::Pushover(config) => Some(Box::new(
+            notifiers::Pushover::create(
+                resolve("user_key", &config.user_key)?,
+                resolve("app_token", &config.app_token)?,
+                config.device,
+                config.sound,
+                config.priority,
+            )
+            .await?,
+        )),
+        NotifierConfig::DingTalk(config) => Some(Box::new(
+            notifiers::DingTalk::create(
+                resolve("webhook_url", &config.webhook_url)?,
+                resolve_optional("secret", &config.secret)?,
+            )
+            .await?,
+        )),
+        NotifierConfig::Feishu(config) => Some(Box::new(
+            notifiers::Feishu::create(
+                resolve("webhook_url", &config.webhook_url)?,
+                resolve_optional("secret", &config.secret)?,
+            )
+            .await?,
+        )),
+        NotifierConfig::WeCom(config) => Some(Box::new(
+            notifiers::WeCom::create(resolve("webhook_url", &config.webhook_url)?).await?,
+        )),
+        NotifierConfig::Bark(config) => Some(Box::new(
+            notifiers::Bark::create(
+                config.server,
+                resolve("device_key", &config.device_key)?,
+                config.group,
+                config.sound,
+                config.level,
+            )
+            .await?,
+        )),
+        NotifierConfig::Mqtt(config) => Some(Box::new(
+            notifiers::Mqtt::create(
+                config.host,
+                config.port,
+                config.tls,
+                config.client_id,
+                config.username,
+                resolve_optional("password", &config.password)?,
+                config.topic,
+                config.qos,
+                config.retain,
+                config.home_assistant,
+                config.discovery_prefix,
+            )
+            .await?,
+        )),
+        NotifierConfig::Exec(config) => Some(Box::new(
+            notifiers::Exec::create(
+                config.command.into_vec(),
+                config.env.into_iter().collect(),
+                config.working_directory,
+                config.stdin,
+                Duration::from_secs(config.timeout),
+                config.success_codes,
+            )
+            .await?,
+        )),
+        NotifierConfig::Empty(_) => None,
     };
     Ok(notifier)
 }
 
-pub(crate) async fn create_provider<S: AsRef<str>>(
-    shutdown: Arc<Shutdown>,
-    kind: S,
-    args: HashMap<String, Value>,
-) -> Result<Box<dyn DynProvider>> {
-    let provider: Box<dyn DynProvider> = match kind.as_ref() {
-        "cloudflare" => {
-            let token = from_args_str!(args, "token");
-            let dns = from_args_str!(args, "dns");
-            let proxied = option_from_args_bool!(args, "proxied");
-            Box::new(providers::Cloudflare::create(token, dns, proxied.unwrap_or_default()).await?)
-        },
-        "godaddy" => {
-            let api_key = from_args_str!(args, "api_key");
-            let secret = from_args_str!(args, "secret");
-            let dns = from_args_str!(args, "dns");
-            Box::new(providers::Godaddy::create(api_key, secret, dns).await?)
-        },
-        "fake" => Box::new(providers::Fake::create(shutdown).await?),
-        _ => {
-            bail!("the kind of provider '{}' not support", kind.as_ref())
+pub(crate) async fn create_provider(shutdown: Arc<Shutdown>, config: ProviderConfig) -> Result<Box<dyn DynProvider>> {
+    let provider: Box<dyn DynProvider> = match config {
+        ProviderConfig::Cloudflare(config) => {
+            Box::new(providers::Cloudflare::create(resolve("token", &config.token)?, config.dns, config.proxied).await?)
         },
+        ProviderConfig::Godaddy(config) => Box::new(
+            providers::Godaddy::create(
+                resolve("api_key", &config.api_key)?,
+                resolve("secret", &config.secret)?,
+                config.dns,
+            )
+            .await?,
+        ),
+        ProviderConfig::Fake(config) => Box::new(providers::Fake::create(shutdown, config.dns).await?),
     };
     Ok(provider)
 }
+
+/// Build the interfaces and add them to the built ones, composite interfaces are built after their members
+pub(crate) async fn build_interfaces(
+    interfaces: HashMap<String, setting::Interface>,
+    built: HashMap<String, Rc<Box<dyn Interface>>>,
+) -> Result<HashMap<String, Rc<Box<dyn Interface>>>> {
+    let mut interface_map = built;
+    let mut pending = interfaces.into_iter().collect::<Vec<_>>();
+    while !pending.is_empty() {
+        // build the interfaces whose dependencies are all ready, composite interfaces need their members first
+        let mut ready = vec![];
+        let mut rest = vec![];
+        for (name, interface) in pending {
+            if interface
+                .config
+                .dependencies()
+                .iter()
+                .all(|v| interface_map.contains_key(v))
+            {
+                ready.push((name, interface));
+            } else {
+                rest.push((name, interface));
+            }
+        }
+        if ready.is_empty() {
+            let names = rest.iter().map(|(name, _)| &**name).collect::<Vec<_>>().join(",");
+            bail!(
+                "can't resolve interface(s) [{}], missing or circular member reference",
+                names
+            );
+        }
+        for (name, interface) in ready {
+            let interface = create_interface(interface.config, &interface_map)
+                .await
+                .map_err(|err| anyhow!("can't build interface '{}': {:#}", name, err))?;
+            interface_map.insert(name, Rc::new(interface));
+        }
+        pending = rest;
+    }
+    Ok(interface_map)
+}
+
+/// Build all the notifiers, wrapped by the outbox and the throttle as configured
+pub(crate) async fn build_notifiers(
+    base: &Base,
+    notifiers: HashMap<String, setting::Notifier>,
+) -> Result<HashMap<String, SubscribedNotifier>> {
+    let mut notifier_map = HashMap::new();
+    for (name, notifier) in notifiers {
+        let notifier = build_notifier(base, &name, notifier)
+            .await
+            .map_err(|err| anyhow!("can't build notifier '{}': {:#}", name, err))?;
+        notifier_map.insert(name, notifier);
+    }
+    Ok(notifier_map)
+}
+
+async fn build_notifier(base: &Base, name: &str, notifier: setting::Notifier) -> Result<SubscribedNotifier> {
+    let events = Rc::new(notifier.events);
+    let throttled = notifier.dedup_window > 0 || notifier.rate_limit > 0 || notifier.digest_interval > 0;
+    let (dedup_window, rate_limit, rate_limit_period, digest_interval) = (
+        Duration::from_secs(notifier.dedup_window),
+        notifier.rate_limit,
+        Duration::from_secs(notifier.rate_limit_period),
+        Duration::from_secs(notifier.digest_interval),
+    );
+    let mut notifier = create_notifier(notifier.config).await?;
+    let mut throttle_state = None;
+    if let Some(outbox_directory) = &base.outbox_directory {
+        if let Some(inner) = notifier {
+            let outbox = notifiers::Outbox::create(
+                inner,
+                outbox_directory,
+                name,
+                Duration::from_secs(base.outbox_retry_interval),
+                Duration::from_secs(base.outbox_max_retry_interval),
+                Duration::from_secs(base.outbox_max_age),
+            )
+            .await?;
+            throttle_state = Some(outbox.directory().join("held_back.state"));
+            notifier = Some(Box::new(outbox));
+        }
+    }
+    // held back events are sent through the outbox too, and kept beside its events
+    if throttled {
+        if let Some(inner) = notifier {
+            let throttle = notifiers::Throttle::create(
+                inner,
+                dedup_window,
+                rate_limit,
+                rate_limit_period,
+                digest_interval,
+                throttle_state,
+            )
+            .await?;
+            notifier = Some(Box::new(throttle));
+        }
+    }
+    Ok((Rc::new(notifier), events))
+}
+
+/// Build all the providers with their ttl and force flag
+pub(crate) async fn build_providers(
+    shutdown: Arc<Shutdown>,
+    providers: HashMap<String, setting::Provider>,
+) -> Result<HashMap<String, ConfiguredProvider>> {
+    let mut provider_map = HashMap::new();
+    for (name, provider) in providers {
+        let force = provider.force;
+        let ttl = provider.ttl;
+        let provider = create_provider(shutdown.clone(), provider.config)
+            .await
+            .map_err(|err| anyhow!("can't build provider '{}': {:#}", name, err))?;
+        provider_map.insert(name, (Rc::new(provider), ttl, force));
+    }
+    Ok(provider_map)
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use futures::future::join_all;
+use log::{debug, warn};
+
+use super::{Changes, Interface};
+use crate::IpType;
+
+/// How the addresses reported by the members are combined
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Strategy {
+    /// Try the members in order until one of them succeeds
+    Fallback,
+    /// Merge and dedupe the addresses of all succeeded members
+    Union,
+    /// Only accept the addresses reported by at least this many members
+    Consensus(usize),
+}
+
+pub struct Composite {
+    members: Vec<(String, Rc<Box<dyn Interface>>)>,
+    strategy: Strategy,
+}
+
+impl Composite {
+    pub fn create(members: Vec<(String, Rc<Box<dyn Interface>>)>, strategy: Strategy) -> Result<Composite> {
+        if members.is_empty() {
+            bail!("composite interface requires at least one member")
+        }
+        if let Strategy::Consensus(quorum) = strategy {
+            if quorum == 0 || quorum > members.len() {
+                bail!("quorum must between 1 and the number of members ({})", members.len())
+            }
+        }
+        Ok(Composite { members, strategy })
+    }
+
+    /// Query every member concurrently, failed members are logged and skipped
+    async fn query_all(&self, family: IpType) -> Result<Vec<Vec<IpAddr>>> {
+        let results = join_all(self.members.iter().map(|(_, member)| member.get_ip(family))).await;
+        let mut succeeded = vec![];
+        let mut last_err = None;
+        for ((name, _), result) in self.members.iter().zip(results) {
+            match result {
+                Ok(ips) => succeeded.push(ips),
+                Err(err) => {
+                    warn!("member '{}' of composite interface failed: {}", name, err);
+                    last_err = Some(err);
+                },
+            }
+        }
+        if succeeded.is_empty() {
+            return Err(last_err.unwrap_or_else(|| anyhow!("all members failed")));
+        }
+        Ok(succeeded)
+    }
+}
+
+#[async_trait(?Send)]
+impl Interface for Composite {
+    async fn get_ip(&self, family: IpType) -> Result<Vec<IpAddr>> {
+        match self.strategy {
+            Strategy::Fallback => {
+                let mut last_err = None;
+                for (name, member) in &self.members {
+                    match member.get_ip(family).await {
+                        Ok(ips) if !ips.is_empty() => {
+                            debug!("use the address(es) of member '{}'", name);
+                            return Ok(ips);
+                        },
+                        Ok(_) => {
+                            warn!("member '{}' of composite interface returns nothing", name);
+                        },
+                        Err(err) => {
+                            warn!("member '{}' of composite interface failed: {}", name, err);
+                            last_err = Some(err);
+                        },
+                    }
+                }
+                Err(last_err.unwrap_or_else(|| anyhow!("all members return nothing for {}", family)))
+            },
+            Strategy::Union => {
+                let mut result = vec![];
+                for ip in self.query_all(family).await?.into_iter().flatten() {
+                    if !result.contains(&ip) {
+                        result.push(ip);
+                    }
+                }
+                Ok(result)
+            },
+            Strategy::Consensus(quorum) => {
+                let mut order = vec![];
+                let mut votes = HashMap::new();
+                for ips in self.query_all(family).await? {
+                    // one member only votes once for the same address
+                    let mut voted = HashSet::new();
+                    for ip in ips.into_iter().filter(|ip| voted.insert(*ip)) {
+                        let count = votes.entry(ip).or_insert(0usize);
+                        if *count == 0 {
+                            order.push(ip);
+                        }
+                        *count += 1;
+                    }
+                }
+                let result = order.into_iter().filter(|ip| votes[ip] >= quorum).collect::<Vec<_>>();
+                if result.is_empty() {
+                    bail!("no address of {} is reported by at least {} member(s)", family, quorum)
+                }
+                Ok(result)
+            },
+        }
+    }
+
+    fn subscribe(&self) -> Changes {
+        Changes::merge(self.members.iter().map(|(_, member)| member.subscribe()))
+    }
+}
@@ -0,0 +1,97 @@
+use std::ffi::OsString;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use log::warn;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::fs;
+use tokio::sync::watch;
+
+use super::{is_family, Changes, Interface};
+use crate::IpType;
+
+pub struct File {
+    path: PathBuf,
+    /// The number of changes, every task waiting on the interface is woken by a change
+    changed: Arc<watch::Sender<u64>>,
+    // keep the watcher alive as long as the interface
+    _watcher: Option<RecommendedWatcher>,
+}
+
+impl File {
+    pub fn create<P: AsRef<Path>>(path: P, watch: bool) -> Result<File> {
+        let path = path.as_ref().to_owned();
+        let changed = Arc::new(watch::Sender::new(0));
+        let watcher = if watch {
+            let file_name = path
+                .file_name()
+                .ok_or_else(|| anyhow!("illegal file path {}", path.display()))?
+                .to_owned();
+            // watch the parent direction, so the file replaced by rename is noticed too
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent.to_owned(),
+                _ => PathBuf::from("."),
+            };
+            let mut watcher = notify::recommended_watcher(watch_handler(file_name, changed.clone()))?;
+            watcher.watch(&parent, RecursiveMode::NonRecursive)?;
+            Some(watcher)
+        } else {
+            None
+        };
+        Ok(File {
+            path,
+            changed,
+            _watcher: watcher,
+        })
+    }
+}
+
+fn watch_handler(file_name: OsString, changed: Arc<watch::Sender<u64>>) -> impl Fn(notify::Result<notify::Event>) {
+    move |event| match event {
+        Ok(event) => {
+            if event.kind.is_access() {
+                return;
+            }
+            if event.paths.iter().any(|path| path.file_name() == Some(&*file_name)) {
+                changed.send_modify(|v| *v = v.wrapping_add(1));
+            }
+        },
+        Err(err) => warn!("watching file failed: {}", err),
+    }
+}
+
+#[async_trait(?Send)]
+impl Interface for File {
+    async fn get_ip(&self, family: IpType) -> Result<Vec<IpAddr>> {
+        let contents = fs::read_to_string(&self.path)
+            .await
+            .map_err(|err| anyhow!("can't read {}: {}", self.path.display(), err))?;
+        let mut result = vec![];
+        // addresses are separated by whitespace or comma, everything after `#` is comment
+        for line in contents.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            for item in line
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|v| !v.is_empty())
+            {
+                let ip = item
+                    .parse::<IpAddr>()
+                    .map_err(|err| anyhow!("can't parse address '{}' in {}: {}", item, self.path.display(), err))?;
+                if is_family(&ip, family) && !result.contains(&ip) {
+                    result.push(ip);
+                }
+            }
+        }
+        if result.is_empty() {
+            bail!("can't find {} address in {}", family, self.path.display())
+        }
+        Ok(result)
+    }
+
+    fn subscribe(&self) -> Changes {
+        Changes::new(self.changed.subscribe())
+    }
+}
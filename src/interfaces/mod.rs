@@ -1,16 +1,85 @@
 use std::net::IpAddr;
+use std::time::Duration;
 
 use anyhow::Result;
 use async_trait::async_trait;
+pub use composite::{Composite, Strategy as CompositeStrategy};
+pub use file::File;
+use futures::future::{self, select_all};
 pub use peer::Peer;
-pub use stock::Stock;
+pub use static_addr::Static;
+pub use stock::{Filter as StockFilter, Selector as StockSelector, Stock};
+use tokio::sync::watch;
+use tokio::time::sleep;
 
 use crate::IpType;
 
+mod composite;
+mod file;
 mod peer;
+mod static_addr;
 mod stock;
 
 #[async_trait(?Send)]
 pub trait Interface {
     async fn get_ip(&self, family: IpType) -> Result<Vec<IpAddr>>;
+
+    /// The changes of the address seen by one task, so it checks the address right away instead of waiting for the
+    /// next tick. Interfaces that can't tell never change.
+    fn subscribe(&self) -> Changes {
+        Changes::default()
+    }
+}
+
+/// The events fired by a single change are taken as one
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A subscription to the changes of an interface, the changes happened while the task is busy are kept until the next
+/// `changed`
+#[derive(Default)]
+pub struct Changes {
+    receivers: Vec<watch::Receiver<u64>>,
+}
+
+impl Changes {
+    fn new(receiver: watch::Receiver<u64>) -> Changes {
+        Changes {
+            receivers: vec![receiver],
+        }
+    }
+
+    /// Changes of any of the subscriptions
+    fn merge(subscriptions: impl IntoIterator<Item = Changes>) -> Changes {
+        Changes {
+            receivers: subscriptions.into_iter().flat_map(|v| v.receivers).collect(),
+        }
+    }
+
+    /// Resolves when the address may have been changed since the last call
+    pub async fn changed(&mut self) {
+        loop {
+            if self.receivers.is_empty() {
+                return future::pending().await;
+            }
+            let (result, index, _) = select_all(self.receivers.iter_mut().map(|v| Box::pin(v.changed()))).await;
+            if result.is_ok() {
+                break;
+            }
+            // the interface is dropped, it never changes again
+            self.receivers.swap_remove(index);
+        }
+        // take the rest events of the same change
+        sleep(DEBOUNCE).await;
+        for receiver in &mut self.receivers {
+            receiver.borrow_and_update();
+        }
+    }
+}
+
+#[inline]
+pub(crate) fn is_family(ip: &IpAddr, family: IpType) -> bool {
+    match family {
+        IpType::V4 => ip.is_ipv4(),
+        IpType::V6 => ip.is_ipv6(),
+    }
 }
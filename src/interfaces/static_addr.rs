@@ -0,0 +1,36 @@
+use std::net::IpAddr;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+
+use super::{is_family, Interface};
+use crate::IpType;
+
+pub struct Static {
+    addresses: Vec<IpAddr>,
+}
+
+impl Static {
+    pub fn create(addresses: Vec<IpAddr>) -> Result<Static> {
+        if addresses.is_empty() {
+            bail!("static interface requires at least one address")
+        }
+        Ok(Static { addresses })
+    }
+}
+
+#[async_trait(?Send)]
+impl Interface for Static {
+    async fn get_ip(&self, family: IpType) -> Result<Vec<IpAddr>> {
+        let result = self
+            .addresses
+            .iter()
+            .filter(|ip| is_family(ip, family))
+            .copied()
+            .collect::<Vec<_>>();
+        if result.is_empty() {
+            bail!("no {} address configured", family)
+        }
+        Ok(result)
+    }
+}
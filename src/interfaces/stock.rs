@@ -1,54 +1,315 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
-use pnet::datalink;
+use log::debug;
+use pnet::datalink::{self, MacAddr, NetworkInterface};
+use pnet::ipnetwork::IpNetwork;
+use regex::Regex;
 
 use super::Interface;
 use crate::IpType;
 
+// Address flags as reported by the kernel, see `include/uapi/linux/if_addr.h`
+const IFA_F_TEMPORARY: u32 = 0x01;
+const IFA_F_DEPRECATED: u32 = 0x20;
+
+/// Rules used to pick which addresses of the interface will be published
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    /// Only keep addresses inside one of these networks, keep all if empty
+    pub include: Vec<IpNetwork>,
+    /// Drop addresses inside one of these networks
+    pub exclude: Vec<IpNetwork>,
+    /// Drop IPv6 temporary (RFC 4941 privacy) addresses
+    pub exclude_temporary: bool,
+    /// Drop IPv6 addresses whose preferred lifetime has expired
+    pub exclude_deprecated: bool,
+    /// Put EUI-64 and other stable addresses before temporary ones
+    pub prefer_stable: bool,
+    /// Publish at most this many addresses
+    pub max_addresses: Option<usize>,
+    /// Accept private ranges (RFC 1918, ULA, CGNAT) as well as global ones
+    pub allow_private: bool,
+}
+
+/// How the network interface is picked
+#[derive(Debug, Clone)]
+pub enum Selector {
+    /// Exactly match the interface name
+    Name(String),
+    /// Match the interface name by a regex, globs are translated into regex
+    Pattern(Regex),
+    /// Match the interface by its MAC address
+    Mac(MacAddr),
+    /// The interface holding the default route of the requested family
+    DefaultRoute,
+}
+
+impl Selector {
+    /// Build a selector from a glob like `ppp*` or `veth?`
+    pub fn glob<P: AsRef<str>>(pattern: P) -> Result<Selector> {
+        let mut expression = String::from("^");
+        for c in pattern.as_ref().chars() {
+            match c {
+                '*' => expression.push_str(".*"),
+                '?' => expression.push('.'),
+                _ => expression.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        expression.push('$');
+        Ok(Selector::Pattern(Regex::new(&expression)?))
+    }
+}
+
+impl Display for Selector {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Selector::Name(name) => write!(f, "name {}", name),
+            Selector::Pattern(pattern) => write!(f, "pattern {}", pattern),
+            Selector::Mac(mac) => write!(f, "mac {}", mac),
+            Selector::DefaultRoute => write!(f, "default route"),
+        }
+    }
+}
+
 pub struct Stock {
-    name: String,
+    selector: Selector,
+    filter: Filter,
 }
 
 impl Stock {
-    pub fn create<N: AsRef<str>>(name: N) -> Result<Stock> {
-        Ok(Stock {
-            name: name.as_ref().to_owned(),
-        })
+    pub fn create(selector: Selector, filter: Filter) -> Result<Stock> {
+        Ok(Stock { selector, filter })
+    }
+
+    fn select_interfaces(&self, family: IpType) -> Result<Vec<NetworkInterface>> {
+        let interfaces = datalink::interfaces();
+        let result = match &self.selector {
+            Selector::Name(name) => interfaces
+                .into_iter()
+                .filter(|interface| &interface.name == name)
+                .collect::<Vec<_>>(),
+            Selector::Pattern(pattern) => interfaces
+                .into_iter()
+                .filter(|interface| pattern.is_match(&interface.name))
+                .collect(),
+            Selector::Mac(mac) => interfaces
+                .into_iter()
+                .filter(|interface| interface.mac.as_ref() == Some(mac))
+                .collect(),
+            Selector::DefaultRoute => {
+                let name = default_route_interface(family)?;
+                debug!("the default route of {} is on {}", family, name);
+                interfaces
+                    .into_iter()
+                    .filter(|interface| interface.name == name)
+                    .collect()
+            },
+        };
+        if result.is_empty() {
+            bail!("can't find interface by {}", self.selector)
+        }
+        Ok(result)
     }
 }
 
 #[async_trait(?Send)]
 impl Interface for Stock {
     async fn get_ip(&self, family: IpType) -> Result<Vec<IpAddr>> {
-        if let Some(interface) = datalink::interfaces()
-            .into_iter()
-            .find(|interface| interface.name == self.name)
-        {
-            let result = interface
-                .ips
-                .into_iter()
-                .map(|ip| ip.ip())
-                // TODO: Switch to `IpAddr::is_global` once stable: https://github.com/rust-lang/rust/issues/27709
-                .filter(is_global)
-                .filter(|ip| {
-                    if family == IpType::V4 && ip.is_ipv4() {
-                        return true;
-                    }
-                    if family == IpType::V6 && ip.is_ipv6() {
-                        return true;
-                    }
-                    false
-                })
-                .collect::<Vec<IpAddr>>();
-            if !result.is_empty() {
-                return Ok(result);
+        let interfaces = self.select_interfaces(family)?;
+        let mut flags = HashMap::new();
+        if family == IpType::V6 {
+            for interface in &interfaces {
+                flags.extend(address_flags(&interface.name));
             }
-            bail!("can't find global address for {}", family)
-        } else {
-            bail!("can't find except interface")
         }
+        let filter = &self.filter;
+        let mut result = interfaces
+            .into_iter()
+            .flat_map(|interface| interface.ips)
+            .map(|ip| ip.ip())
+            .filter(|ip| {
+                if filter.allow_private {
+                    is_usable(ip)
+                } else {
+                    // TODO: Switch to `IpAddr::is_global` once stable: https://github.com/rust-lang/rust/issues/27709
+                    is_global(ip)
+                }
+            })
+            .filter(|ip| {
+                if family == IpType::V4 && ip.is_ipv4() {
+                    return true;
+                }
+                if family == IpType::V6 && ip.is_ipv6() {
+                    return true;
+                }
+                false
+            })
+            .filter(|ip| filter.include.is_empty() || filter.include.iter().any(|net| net.contains(*ip)))
+            .filter(|ip| !filter.exclude.iter().any(|net| net.contains(*ip)))
+            .filter(|ip| {
+                let flags = ip_flags(&flags, ip);
+                if filter.exclude_temporary && flags & IFA_F_TEMPORARY != 0 {
+                    debug!("skip temporary address {}", ip);
+                    return false;
+                }
+                if filter.exclude_deprecated && flags & IFA_F_DEPRECATED != 0 {
+                    debug!("skip deprecated address {}", ip);
+                    return false;
+                }
+                true
+            })
+            .collect::<Vec<IpAddr>>();
+        if filter.prefer_stable {
+            // stable sort, so addresses with the same rank keep the order reported by the system
+            result.sort_by_key(|ip| stability_rank(ip, ip_flags(&flags, ip)));
+        }
+        if let Some(max_addresses) = filter.max_addresses {
+            result.truncate(max_addresses);
+        }
+        if !result.is_empty() {
+            return Ok(result);
+        }
+        bail!("can't find global address for {}", family)
+    }
+}
+
+#[inline]
+fn ip_flags(flags: &HashMap<Ipv6Addr, u32>, ip: &IpAddr) -> u32 {
+    match ip {
+        IpAddr::V4(_) => 0,
+        IpAddr::V6(ip) => flags.get(ip).copied().unwrap_or_default(),
+    }
+}
+
+/// Lower is better: EUI-64, other stable, temporary, deprecated
+fn stability_rank(ip: &IpAddr, flags: u32) -> u8 {
+    if flags & IFA_F_DEPRECATED != 0 {
+        return 3;
+    }
+    if flags & IFA_F_TEMPORARY != 0 {
+        return 2;
+    }
+    match ip {
+        IpAddr::V6(ip) if is_eui64(ip) => 0,
+        _ => 1,
+    }
+}
+
+/// The interface identifier is derived from a MAC address (`xx:xx:xxff:fexx:xxxx`)
+#[inline]
+fn is_eui64(ip: &Ipv6Addr) -> bool {
+    let octets = ip.octets();
+    octets[11] == 0xff && octets[12] == 0xfe
+}
+
+/// Read IPv6 address flags of the interface from `/proc/net/if_inet6`
+///
+/// Every line looks like `<address> <ifindex> <prefix_len> <scope> <flags> <name>`,
+/// `pnet` doesn't expose these flags so we parse them ourselves.
+#[cfg(target_os = "linux")]
+fn address_flags(name: &str) -> HashMap<Ipv6Addr, u32> {
+    let contents = match std::fs::read_to_string("/proc/net/if_inet6") {
+        Ok(v) => v,
+        Err(err) => {
+            debug!("can't read address flags: {}", err);
+            return HashMap::new();
+        },
+    };
+    contents
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            if fields.len() != 6 || fields[5] != name {
+                return None;
+            }
+            let ip = u128::from_str_radix(fields[0], 16).ok()?;
+            let flags = u32::from_str_radix(fields[4], 16).ok()?;
+            Some((Ipv6Addr::from(ip), flags))
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn address_flags(_name: &str) -> HashMap<Ipv6Addr, u32> {
+    HashMap::new()
+}
+
+/// Find the interface holding the default route with the lowest metric
+///
+/// IPv4 routes come from `/proc/net/route` (`<iface> <destination> <gateway> <flags> <refcnt> <use> <metric> <mask> ...`),
+/// IPv6 routes come from `/proc/net/ipv6_route`
+/// (`<destination> <prefix_len> <source> <prefix_len> <next_hop> <metric> <refcnt> <use> <flags> <iface>`).
+#[cfg(target_os = "linux")]
+fn default_route_interface(family: IpType) -> Result<String> {
+    const RTF_UP: u32 = 0x0001;
+    const RTF_REJECT: u32 = 0x0200;
+
+    let routes = match family {
+        IpType::V4 => std::fs::read_to_string("/proc/net/route")?
+            .lines()
+            .skip(1)
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                if fields.len() < 8 || fields[1] != "00000000" || fields[7] != "00000000" {
+                    return None;
+                }
+                let flags = u32::from_str_radix(fields[3], 16).ok()?;
+                let metric = fields[6].parse::<u32>().ok()?;
+                Some((fields[0].to_owned(), flags, metric))
+            })
+            .collect::<Vec<_>>(),
+        IpType::V6 => std::fs::read_to_string("/proc/net/ipv6_route")?
+            .lines()
+            .filter_map(|line| {
+                let fields = line.split_whitespace().collect::<Vec<_>>();
+                if fields.len() != 10 || u128::from_str_radix(fields[0], 16).ok()? != 0 || fields[1] != "00" {
+                    return None;
+                }
+                let metric = u32::from_str_radix(fields[5], 16).ok()?;
+                let flags = u32::from_str_radix(fields[8], 16).ok()?;
+                Some((fields[9].to_owned(), flags, metric))
+            })
+            .collect(),
+    };
+    routes
+        .into_iter()
+        .filter(|(_, flags, _)| flags & RTF_UP != 0 && flags & RTF_REJECT == 0)
+        .min_by_key(|(_, _, metric)| *metric)
+        .map(|(name, _, _)| name)
+        .ok_or_else(|| anyhow!("can't find default route for {}", family))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_route_interface(_family: IpType) -> Result<String> {
+    bail!("select interface by default route is only supported on linux")
+}
+
+/// Accept everything that can be routed at all, including private ranges
+#[inline]
+fn is_usable(addr: &IpAddr) -> bool {
+    match addr {
+        IpAddr::V4(ip) => {
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_multicast()
+                || ip.is_broadcast()
+                || ip.is_documentation())
+        },
+        IpAddr::V6(ip) => {
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unicast link-local (`fe80::/10`)
+                || (ip.segments()[0] & 0xffc0) == 0xfe80
+                // IPv4-mapped Address (`::ffff:0:0/96`)
+                || matches!(ip.segments(), [0, 0, 0, 0, 0, 0xffff, _, _])
+                || (ip.segments()[0] == 0x2001 && ip.segments()[1] == 0xdb8))
+        },
     }
 }
 
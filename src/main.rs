@@ -2,14 +2,16 @@ use std::collections::HashMap;
 use std::env::{current_dir, set_current_dir};
 use std::fmt::{Display, Formatter};
 use std::fs::File;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Result};
-use clap::Parser;
-use factory::{create_interface, create_notifier, create_provider};
-use future::join_all;
+use chrono::Local;
+use clap::{Parser, Subcommand};
+use event::{ChangeEvent, Event, EventKind, FailureEvent, RecoveryEvent};
+use factory::ConfiguredProvider;
 use futures::prelude::*;
 use interfaces::Interface;
 use log::{debug, error, info, warn, LevelFilter};
@@ -23,22 +25,34 @@ use log4rs::encode::pattern::PatternEncoder;
 use log4rs::filter::threshold::ThresholdFilter;
 use notifiers::Notifier;
 use providers::DynProvider;
-use setting::Setting;
+use serde::{Deserialize, Serialize};
+use setting::{ConfigWatcher, Setting};
 use shutdown::Shutdown;
-use tokio::time::{interval_at, sleep, Duration, Instant};
-use tokio::{fs, join, pin, select, signal};
-
+use supervisor::Supervisor;
+use tokio::task::LocalSet;
+use tokio::time::{interval_at, sleep, sleep_until, Duration, Instant};
+use tokio::{select, signal};
+
+mod check;
+mod detect;
+mod event;
 mod factory;
 mod interfaces;
 mod notifiers;
+mod once;
 mod providers;
+mod records;
+mod secret;
 mod setting;
 mod shutdown;
+mod supervisor;
 mod updater;
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum IpType {
+    #[serde(rename = "ipv4")]
     V4,
+    #[serde(rename = "ipv6")]
     V6,
 }
 
@@ -99,12 +113,14 @@ fn setup_logger(level: LevelFilter, log_direction: PathBuf) -> Result<log4rs::Ha
 }
 
 async fn run_task(
+    task_name: &str,
     families: &[IpType],
-    provider: (Rc<Box<dyn DynProvider>>, u32, bool),
+    provider: (&str, Rc<Box<dyn DynProvider>>, u32, bool),
     interface: Rc<Box<dyn Interface>>,
-    notifiers: Vec<Rc<Option<Box<dyn Notifier>>>>,
-) -> Result<()> {
-    let (provider, ttl, force) = provider;
+    notifiers: &[SubscribedNotifier],
+) -> Result<Vec<ChangeEvent>> {
+    let (provider_name, provider, ttl, force) = provider;
+    let mut changes = vec![];
     for family in families {
         let target_ips = interface.get_ip(*family).await?;
         let ips_str = target_ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
@@ -120,103 +136,137 @@ async fn run_task(
             continue;
         }
         info!("got ip(s) from interface: [{}]", ips_str);
-        let update_ips = provider.check_and_update(&target_ips, ttl, force, *family).await?;
-        if !update_ips.is_empty() {
-            for notifier in notifiers.clone() {
-                if let Some(notifier) = &*notifier {
-                    notifier.send(&update_ips).await?;
-                }
-            }
+        let change_set = provider.check_and_update(&target_ips, ttl, force, *family).await?;
+        if !change_set.is_empty() {
+            let change = ChangeEvent::new(
+                task_name,
+                provider_name,
+                provider.domain(),
+                *family,
+                change_set.old_ips,
+                target_ips,
+                change_set.changes,
+            );
+            notify(notifiers, &Event::Changed(change.clone())).await;
+            changes.push(change);
         }
     }
-    Ok(())
+    Ok(changes)
 }
 
-async fn run(shutdown: Arc<Shutdown>, setting: Setting) -> Result<()> {
-    let base = setting.base;
-    debug!("building interfaces");
-    let mut interface_map = HashMap::new();
-    for (name, interface) in setting.interfaces {
-        let interface = create_interface(interface.kind, interface.args).await?;
-        interface_map.insert(name, Rc::new(interface));
-    }
-
-    debug!("building notifiers");
-    let mut notifier_map = HashMap::new();
-    for (name, notifier) in setting.notifiers {
-        let notifier = create_notifier(notifier.kind, notifier.args).await?;
-        notifier_map.insert(name, Rc::new(notifier));
-    }
-
-    debug!("building providers");
-    let mut provider_map = HashMap::new();
-    for (name, provider) in setting.providers {
-        let force = provider.force;
-        let ttl = provider.ttl;
-        let provider = create_provider(shutdown.clone(), provider.kind, provider.args).await?;
-        provider_map.insert(name, (Rc::new(provider), ttl, force));
-    }
+/// A notifier with the kinds of event it subscribed
+type SubscribedNotifier = (Rc<Option<Box<dyn Notifier>>>, Rc<Vec<EventKind>>);
 
-    let create_task = move |start_delay: Duration, task_name: String, task: setting::Task| -> Result<_> {
-        let family = &*task.family;
-        let families: &[IpType] = match family {
-            "ipv4" => &[IpType::V4],
-            "ipv6" => &[IpType::V6],
-            "all" => &[IpType::V4, IpType::V6],
-            _ => {
-                bail!("unknown family {}", family)
-            },
-        };
-        let mut notifiers = vec![];
-        for notifier in &task.notifiers {
-            let notifier = notifier_map
-                .get(notifier)
-                .ok_or_else(|| anyhow!("can't find notifier define"))?
-                .clone();
-            notifiers.push(notifier);
+/// Send the event by the notifiers subscribed it, a failed notifier doesn't stop the others
+async fn notify(notifiers: &[SubscribedNotifier], event: &Event) {
+    for (notifier, events) in notifiers {
+        if !events.contains(&event.kind()) {
+            continue;
         }
-        let interface = interface_map
-            .get(&*task.interface)
-            .ok_or_else(|| anyhow!("can't find interface define"))?
-            .clone();
-        let provider = provider_map
-            .get(&*task.provider)
-            .ok_or_else(|| anyhow!("can't find provider define"))?
-            .clone();
-        let interval_duration = Duration::from_secs(task.interval as u64);
-        Ok(async move {
-            let start = Instant::now() + start_delay;
-            let mut check_timer = interval_at(start, interval_duration);
-            loop {
-                check_timer.tick().await;
-                if let Err(err) = run_task(families, provider.clone(), interface.clone(), notifiers.clone()).await {
-                    warn!("task '{task_name}' happen error: {err:#?}");
-                }
+        if let Some(notifier) = &**notifier {
+            if let Err(err) = notifier.send(event).await {
+                warn!(
+                    "task '{}' can't send {} notification: {err:#?}",
+                    event.task(),
+                    event.kind()
+                );
             }
-        })
-    };
-
-    debug!("building task");
-    let shutdown_signal = shutdown.receive();
-
-    let mut task_futures = Vec::new();
-    for (i, (task_name, task)) in setting.tasks.into_iter().enumerate() {
-        let future = create_task(
-            Duration::from_secs(base.task_startup_interval * i as u64),
-            task_name,
-            task,
-        )?;
-        task_futures.push(future);
+        }
     }
+}
 
-    debug!("starting tasks");
-    select! {
-        _ = shutdown_signal => {},
-        _ = join_all(task_futures) => {
-            warn!("all tasks are finished");
-        },
+/// Build the endless loop of a task from the built components
+fn create_task(
+    start_delay: Duration,
+    task_name: String,
+    task: &setting::Task,
+    interface_map: &HashMap<String, Rc<Box<dyn Interface>>>,
+    notifier_map: &HashMap<String, SubscribedNotifier>,
+    provider_map: &HashMap<String, ConfiguredProvider>,
+) -> Result<impl Future<Output = ()>> {
+    let families = task.families()?;
+    let mut notifiers = vec![];
+    for notifier in &task.notifiers {
+        let notifier = notifier_map
+            .get(notifier)
+            .ok_or_else(|| anyhow!("can't find notifier define"))?
+            .clone();
+        notifiers.push(notifier);
     }
-    Ok(())
+    let interface = interface_map
+        .get(&*task.interface)
+        .ok_or_else(|| anyhow!("can't find interface define"))?
+        .clone();
+    let provider = provider_map
+        .get(&*task.provider)
+        .ok_or_else(|| anyhow!("can't find provider define"))?
+        .clone();
+    let provider_name = task.provider.clone();
+    let interval_duration = Duration::from_secs(task.interval as u64);
+    let failure_threshold = task.failure_threshold;
+    // subscribe before running, so the changes while the task is busy aren't missed
+    let mut changes = interface.subscribe();
+    Ok(async move {
+        let start = Instant::now() + start_delay;
+        let mut check_timer = interval_at(start, interval_duration);
+        // consecutive failures and the time of the first one
+        let mut failures = 0;
+        let mut failing_since = Local::now();
+        loop {
+            select! {
+                _ = check_timer.tick() => {},
+                _ = changes.changed() => {
+                    info!("task '{task_name}' triggered by interface change");
+                },
+            }
+            let (provider, ttl, force) = provider.clone();
+            let domain = provider.domain().map(|v| v.to_owned());
+            let provider = (&*provider_name, provider, ttl, force);
+            let mut events = vec![];
+            match run_task(&task_name, families, provider, interface.clone(), &notifiers).await {
+                Ok(_) => {
+                    if failures > 0 {
+                        events.push(Event::Recovered(RecoveryEvent {
+                            task: task_name.clone(),
+                            provider: provider_name.clone(),
+                            domain,
+                            failures,
+                            since: failing_since,
+                            timestamp: Local::now(),
+                            hostname: notifiers::hostname(),
+                        }));
+                        failures = 0;
+                    }
+                },
+                Err(err) => {
+                    warn!("task '{task_name}' happen error: {err:#?}");
+                    failures += 1;
+                    if failures == 1 {
+                        failing_since = Local::now();
+                    }
+                    let event = FailureEvent {
+                        task: task_name.clone(),
+                        provider: provider_name.clone(),
+                        domain,
+                        error: format!("{err:#}"),
+                        failures,
+                        since: failing_since,
+                        timestamp: Local::now(),
+                        hostname: notifiers::hostname(),
+                    };
+                    // with a threshold of 1 the first failure is already a repeated one, it's only reported once
+                    if failures == failure_threshold {
+                        events.push(Event::FailedRepeatedly(event));
+                    } else if failures == 1 {
+                        events.push(Event::Failed(event));
+                    }
+                },
+            }
+            for event in events {
+                notify(&notifiers, &event).await;
+            }
+        }
+    })
 }
 
 /// ┌┬┐┌┬┐┌┐┌┌─┐   ┬─┐┌─┐
@@ -234,9 +284,15 @@ struct Opts {
     /// Path of config file
     #[arg(short, long, default_value = "config.toml")]
     config: String,
+    /// Format of config file, it will be detected by the extension if not specified
+    #[arg(long, value_enum)]
+    config_format: Option<setting::Format>,
     /// Verbose mode (-v, -vv, -vvv, etc.)
     #[arg(short, long, action = clap::ArgAction::Count)]
     verbose: u8,
+    /// Reload the config when the config files are changed, like receiving SIGHUP
+    #[arg(short, long)]
+    watch: bool,
     /// Nothing to output if specified
     #[arg(short, long)]
     silence: bool,
@@ -253,129 +309,245 @@ struct Opts {
     /// Current direction, it will use '.' if not specified
     #[arg(short = 'L', long)]
     log_direction: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the config file and exit, the exit code is non-zero if there is any problem
+    Check {
+        /// Also build everything and query the records of each provider, to check the secrets and credentials
+        #[arg(long)]
+        live: bool,
+    },
+    /// Run the tasks once and exit, the exit code is non-zero if any task failed
+    RunOnce {
+        /// The names of the tasks, all the tasks if not specified
+        tasks: Vec<String>,
+    },
+    /// Manage the records of a provider directly
+    Records {
+        #[command(subcommand)]
+        command: RecordsCommand,
+    },
+    /// Print the addresses an interface gets
+    Detect {
+        /// The name of the interface
+        interface: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RecordsCommand {
+    /// List the records
+    List {
+        /// The name of the provider
+        provider: String,
+    },
+    /// Make the records of a family the same as the addresses, the other family is left alone
+    Set {
+        /// The name of the provider
+        provider: String,
+        #[arg(required = true)]
+        ips: Vec<IpAddr>,
+    },
+    /// Delete the records of an address
+    Delete {
+        /// The name of the provider
+        provider: String,
+        ip: IpAddr,
+    },
+}
+
+/// Run the subcommand with the config and exit, the exit code is non-zero if it failed
+fn exit_with<F, T>(setting: Result<Setting>, command: F) -> !
+where
+    F: FnOnce(Setting) -> T,
+    T: Future<Output = bool>,
+{
+    let succeeded = match setting {
+        Ok(setting) => build_tokio_runtime().block_on(command(setting)),
+        Err(err) => {
+            println!("error: {}", err);
+            false
+        },
+    };
+    std::process::exit(if succeeded { 0 } else { 1 });
+}
+
+/// Read and parse the config file, or the config files in the directory
+fn read_setting(config_file: &str, config_format: Option<setting::Format>) -> Result<Setting> {
+    debug!("reading config from: {}", config_file);
+    Setting::load(config_file, config_format).map_err(|err| anyhow!("can't load config: {:#}", err))
+}
+
+/// Read the config and make sure it can be run
+fn load_setting(config_file: &str, config_format: Option<setting::Format>) -> Result<Setting> {
+    let setting = read_setting(config_file, config_format)?;
+    let problems = setting.validate();
+    if !problems.is_empty() {
+        bail!("invalid config: {}", problems.join("; "));
+    }
+    Ok(setting)
 }
 
-async fn real_main(config_file: String, log_level: LevelFilter, log_direction: PathBuf) {
+async fn real_main(
+    config_file: String,
+    config_format: Option<setting::Format>,
+    watch: bool,
+    log_level: LevelFilter,
+    log_direction: PathBuf,
+) {
     // setup logger
     setup_logger(log_level, log_direction).expect("can't setup logger");
 
-    let shutdown = Arc::new(Shutdown::new());
-    let mut retry = false;
-    'outer: loop {
-        // loading config
-        debug!("reading config from: {}", &config_file);
-        let setting_contents = match fs::read_to_string(&config_file).await {
-            Ok(v) => v,
-            Err(err) => {
-                error!("can't read config: {}", err);
-                return;
-            },
-        };
-        let setting: Setting = match toml::from_str(&setting_contents) {
-            Ok(v) => v,
-            Err(err) => {
-                error!("can't parse config: {}", err);
+    // loading config
+    let setting = match load_setting(&config_file, config_format) {
+        Ok(v) => v,
+        Err(err) => {
+            error!("{}", err);
+            return;
+        },
+    };
+
+    // the tasks are spawned on the local set, the components they share aren't thread safe
+    let local = LocalSet::new();
+    local.run_until(serve(config_file, config_format, watch, setting)).await;
+}
+
+async fn serve(config_file: String, config_format: Option<setting::Format>, watch: bool, setting: Setting) {
+    #[cfg(target_os = "linux")]
+    let (mut sighup, mut sigterm) = {
+        use tokio::signal::unix::{signal, SignalKind};
+        match (signal(SignalKind::hangup()), signal(SignalKind::terminate())) {
+            (Ok(sighup), Ok(sigterm)) => (sighup, sigterm),
+            (Err(err), _) | (_, Err(err)) => {
+                error!("unable to listen for signals: {}", err);
                 return;
             },
-        };
-        info!("started");
-        #[cfg(target_os = "linux")]
-        {
-            use sd_notify::NotifyState;
-            let _ = sd_notify::notify(true, &[NotifyState::Reloading]);
-            let _ = sd_notify::notify(true, &[NotifyState::Ready]);
         }
+    };
 
-        loop {
-            // prepare main logic
-            let run_task = run(shutdown.clone(), setting.clone());
-            pin!(run_task);
-
-            let reload_sig = async move {
-                #[cfg(target_os = "linux")]
-                {
-                    use tokio::signal::unix::{signal, SignalKind};
-                    let mut sighup = signal(SignalKind::hangup())?;
-                    sighup.recv().await;
-                    return Ok(());
-                }
-                #[cfg(not(target_os = "linux"))]
-                {
-                    let () = future::pending().await;
-                    unreachable!();
-                }
-                #[allow(unreachable_code)]
-                Result::<_>::Ok(())
-            };
-            pin!(reload_sig);
-
-            let exit_sig = async move {
-                #[cfg(target_os = "linux")]
-                {
-                    use tokio::signal::unix::{signal, SignalKind};
-                    let mut sigterm = signal(SignalKind::terminate())?;
-                    select! {
-                        _ = sigterm.recv() => {},
-                        v = signal::ctrl_c() => v?,
-                    };
-                    return Ok(());
-                }
-                #[cfg(not(target_os = "linux"))]
-                {
-                    signal::ctrl_c().await?;
-                    return Ok(());
-                }
-                #[allow(unreachable_code)]
-                Result::<_>::Ok(())
-            };
-            pin!(exit_sig);
-
-            select! {
-                result = exit_sig => {
-                    match result {
-                        Ok(()) => {
-                            info!("receive signal interrupt -> exec graceful shutdown");
-                            let (result, _) = join!(run_task, shutdown.shutdown());
-                            if let Err(err) = result {
-                                error!("unexpected error: {}", err);
-                            }
-                            info!("shutdown");
-                            break 'outer;
-                        },
-                        Err(err) => {
-                            error!("unable to listen for shutdown signal: {}", err);
-                            return;
-                        },
+    let shutdown = Arc::new(Shutdown::new());
+    let mut supervisor = Supervisor::new(shutdown.clone());
+    let mut watcher = None;
+    let mut started = false;
+    // the config waiting to be applied, it's retried until all the components are built
+    let mut pending = Some(setting);
+    let mut retry_at = Instant::now();
+    loop {
+        if let Some(setting) = pending.take() {
+            let retry_timeout = Duration::from_secs(setting.base.task_retry_timeout);
+            let sources = setting.sources.clone();
+            match supervisor.apply(setting.clone()).await {
+                Ok(()) => {
+                    if started {
+                        info!("config reloaded");
+                    } else {
+                        info!("started");
+                        started = true;
                     }
-                },
-                result = reload_sig => {
-                    match result {
-                        Ok(()) => {
-                            info!("receive reload signal -> reload setting");
-                            // waiting for unfinished tasks to finish before the signal
-                            // and then reload the new setting
-                            let (result, _) = join!(run_task, shutdown.shutdown());
-                            if let Err(err) = result {
-                                error!("unexpected error: {}", err);
-                            }
-                            break;
-                        },
-                        Err(err) => {
-                            error!("unable to listen for reload signal: {}", err);
-                            return;
-                        },
+                    #[cfg(target_os = "linux")]
+                    {
+                        let _ = sd_notify::notify(true, &[sd_notify::NotifyState::Ready]);
+                    }
+                    if watch {
+                        watcher = ConfigWatcher::create(&sources)
+                            .map_err(|err| warn!("can't watch config: {}", err))
+                            .ok();
                     }
                 },
-                result = &mut run_task, if !retry => {
-                    if let Err(err) = result {
-                        error!("unexpected error: {}", err);
-                        retry = true
+                Err(err) => {
+                    if started {
+                        error!(
+                            "can't apply the new config, keep running the current one and retry in {}s: {:#}",
+                            retry_timeout.as_secs(),
+                            err
+                        );
+                        // the current config is still running, the reload isn't in progress while waiting to retry
+                        #[cfg(target_os = "linux")]
+                        {
+                            let _ = sd_notify::notify(true, &[sd_notify::NotifyState::Ready]);
+                        }
+                    } else {
+                        error!("can't start, retry in {}s: {:#}", retry_timeout.as_secs(), err);
                     }
+                    pending = Some(setting);
+                    retry_at = Instant::now() + retry_timeout;
                 },
-                _ = sleep(Duration::from_secs(10)), if retry => {
-                    retry = false
+            }
+        }
+
+        let exit_sig = async {
+            #[cfg(target_os = "linux")]
+            {
+                select! {
+                    _ = sigterm.recv() => Ok(()),
+                    v = signal::ctrl_c() => v,
+                }
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                signal::ctrl_c().await
+            }
+        };
+        let reload_sig = async {
+            #[cfg(target_os = "linux")]
+            {
+                sighup.recv().await;
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                future::pending::<()>().await;
+            }
+        };
+        let config_changed = async {
+            match &watcher {
+                Some(watcher) => {
+                    watcher.changed().await;
+                    // editors usually write a file in several steps
+                    sleep(Duration::from_millis(500)).await;
                 },
+                None => future::pending().await,
             }
+        };
+
+        select! {
+            result = exit_sig => {
+                match result {
+                    Ok(()) => info!("receive signal interrupt -> exec graceful shutdown"),
+                    Err(err) => error!("unable to listen for shutdown signal: {}", err),
+                }
+                supervisor.stop().await;
+                shutdown.shutdown().await;
+                info!("shutdown");
+                return;
+            },
+            _ = reload_sig => {
+                info!("receive reload signal -> reload setting");
+            },
+            _ = config_changed => {
+                info!("config changed -> reload setting");
+            },
+            _ = sleep_until(retry_at), if pending.is_some() => {
+                continue;
+            },
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let _ = sd_notify::notify(true, &[sd_notify::NotifyState::Reloading]);
+        }
+        match load_setting(&config_file, config_format) {
+            Ok(setting) => pending = Some(setting),
+            Err(err) => {
+                error!("keep running the current config, {}", err);
+                #[cfg(target_os = "linux")]
+                {
+                    let _ = sd_notify::notify(true, &[sd_notify::NotifyState::Ready]);
+                }
+            },
         }
     }
 }
@@ -422,6 +594,29 @@ fn main() {
 
     let log_direction = opts.log_direction.unwrap_or_else(|| current_direction.clone());
 
+    if let Some(command) = opts.command {
+        let shutdown = Arc::new(Shutdown::new());
+        match command {
+            Command::Check { live } => exit_with(read_setting(&opts.config, opts.config_format), |setting| {
+                check::check(shutdown, setting, live)
+            }),
+            Command::RunOnce { tasks } => {
+                setup_logger(log_level, log_direction).expect("can't setup logger");
+                exit_with(load_setting(&opts.config, opts.config_format), |setting| {
+                    once::run_once(shutdown, setting, tasks)
+                })
+            },
+            Command::Records { command } => exit_with(read_setting(&opts.config, opts.config_format), |setting| {
+                records::records(shutdown, setting, command)
+            }),
+            Command::Detect { interface } => {
+                exit_with(read_setting(&opts.config, opts.config_format), |setting| async move {
+                    detect::detect(setting, &interface).await
+                })
+            },
+        }
+    }
+
     #[cfg(target_family = "unix")]
     {
         use daemonize::Daemonize;
@@ -442,7 +637,13 @@ fn main() {
             match daemonize.start() {
                 Ok(_) => {
                     let runtime = build_tokio_runtime();
-                    runtime.block_on(real_main(opts.config, log_level, log_direction));
+                    runtime.block_on(real_main(
+                        opts.config,
+                        opts.config_format,
+                        opts.watch,
+                        log_level,
+                        log_direction,
+                    ));
                 },
                 Err(err) => {
                     error!("can't start daemonize: {}", err);
@@ -451,7 +652,13 @@ fn main() {
         } else {
             info!("starting");
             let runtime = build_tokio_runtime();
-            runtime.block_on(real_main(opts.config, log_level, log_direction));
+            runtime.block_on(real_main(
+                opts.config,
+                opts.config_format,
+                opts.watch,
+                log_level,
+                log_direction,
+            ));
         }
     }
 
@@ -459,6 +666,12 @@ fn main() {
     {
         info!("starting");
         let runtime = build_tokio_runtime();
-        runtime.block_on(real_main(opts.config, log_level, log_direction));
+        runtime.block_on(real_main(
+            opts.config,
+            opts.config_format,
+            opts.watch,
+            log_level,
+            log_direction,
+        ));
     }
 }
@@ -0,0 +1,89 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::event::Event;
+use crate::Notifier;
+
+const SERVER: &str = "https://api.day.app";
+
+pub struct Bark {
+    url: String,
+    device_key: String,
+    group: Option<String>,
+    sound: Option<String>,
+    level: Option<String>,
+    client: Client,
+}
+
+impl Bark {
+    pub async fn create<S: AsRef<str>>(
+        server: Option<S>,
+        device_key: S,
+        group: Option<S>,
+        sound: Option<S>,
+        level: Option<S>,
+    ) -> Result<Bark> {
+        if let Some(level) = &level {
+            if !["active", "timeSensitive", "passive", "critical"].contains(&level.as_ref()) {
+                bail!("unknown bark level {}", level.as_ref())
+            }
+        }
+        let server = match &server {
+            None => SERVER,
+            Some(server) => server.as_ref(),
+        };
+        let client = reqwest::Client::builder().build()?;
+        Ok(Bark {
+            url: format!("{}/push", server.trim_end_matches('/')),
+            device_key: device_key.as_ref().to_owned(),
+            group: group.map(|v| v.as_ref().to_owned()),
+            sound: sound.map(|v| v.as_ref().to_owned()),
+            level: level.map(|v| v.as_ref().to_owned()),
+            client,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Notifier for Bark {
+    async fn send(&self, event: &Event) -> Result<()> {
+        /// See <https://bark.day.app/#/tutorial?id=%e8%af%b7%e6%b1%82%e5%8f%82%e6%95%b0>
+        #[derive(Serialize)]
+        struct PushParams<'a> {
+            device_key: &'a str,
+            title: String,
+            body: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            group: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            sound: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            level: Option<&'a str>,
+        }
+
+        #[derive(Deserialize)]
+        struct PushResponse {
+            code: i64,
+            #[serde(default)]
+            message: String,
+        }
+
+        let params = PushParams {
+            device_key: &self.device_key,
+            title: event.title(),
+            body: event.text(),
+            group: self.group.as_deref(),
+            sound: self.sound.as_deref(),
+            level: self.level.as_deref(),
+        };
+        let response = self.client.post(&self.url).json(&params).send().await?;
+        let status = response.status();
+        let result = response.json::<PushResponse>().await?;
+        if result.code != 200 {
+            bail!("{status}: {} {}", result.code, result.message)
+        }
+        Ok(())
+    }
+}
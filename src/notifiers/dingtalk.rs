@@ -0,0 +1,76 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use base64::prelude::*;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::hmac_sha256;
+use crate::event::Event;
+use crate::Notifier;
+
+pub struct DingTalk {
+    webhook_url: String,
+    secret: Option<String>,
+    client: Client,
+}
+
+impl DingTalk {
+    pub async fn create<S: AsRef<str>>(webhook_url: S, secret: Option<S>) -> Result<DingTalk> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(DingTalk {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            secret: secret.map(|v| v.as_ref().to_owned()),
+            client,
+        })
+    }
+}
+
+/// See <https://open.dingtalk.com/document/orgapp/customize-robot-security-settings>
+fn sign(secret: &str, timestamp: i64) -> String {
+    let string_to_sign = format!("{}\n{}", timestamp, secret);
+    BASE64_STANDARD.encode(hmac_sha256(secret.as_bytes(), string_to_sign.as_bytes()))
+}
+
+fn build_markdown(event: &Event) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "#### {}\n", event.title());
+    for (label, value) in event.details() {
+        let _ = writeln!(text, "- **{}**: {}", label, value.replace('\n', ", "));
+    }
+    text
+}
+
+#[async_trait(?Send)]
+impl Notifier for DingTalk {
+    async fn send(&self, event: &Event) -> Result<()> {
+        #[derive(Deserialize)]
+        struct RobotResponse {
+            errcode: i64,
+            errmsg: String,
+        }
+
+        let mut request = self.client.post(&self.webhook_url);
+        if let Some(secret) = &self.secret {
+            let timestamp = Utc::now().timestamp_millis();
+            request = request.query(&[("timestamp", timestamp.to_string()), ("sign", sign(secret, timestamp))]);
+        }
+        let message = json!({
+            "msgtype": "markdown",
+            "markdown": {
+                "title": event.title(),
+                "text": build_markdown(event),
+            },
+        });
+        let response = request.json(&message).send().await?;
+        let status = response.status();
+        let result = response.json::<RobotResponse>().await?;
+        if result.errcode != 0 {
+            bail!("{status}: {} {}", result.errcode, result.errmsg)
+        }
+        Ok(())
+    }
+}
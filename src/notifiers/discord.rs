@@ -0,0 +1,82 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::post_json;
+use crate::event::{Event, EventKind};
+use crate::Notifier;
+
+// Discord rejects empty field values and values longer than 1024 characters
+const MAX_FIELD_VALUE: usize = 1024;
+
+pub struct Discord {
+    webhook_url: String,
+    username: Option<String>,
+    avatar_url: Option<String>,
+    client: Client,
+}
+
+impl Discord {
+    pub async fn create<S: AsRef<str>>(webhook_url: S, username: Option<S>, avatar_url: Option<S>) -> Result<Discord> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(Discord {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            username: username.map(|v| v.as_ref().to_owned()),
+            avatar_url: avatar_url.map(|v| v.as_ref().to_owned()),
+            client,
+        })
+    }
+}
+
+/// See <https://discord.com/developers/docs/resources/message#embed-object>
+fn build_message(event: &Event) -> Value {
+    let color = match event.kind() {
+        EventKind::Changed => 0x3498db,
+        EventKind::Failed => 0xe67e22,
+        EventKind::FailedRepeatedly => 0xe74c3c,
+        EventKind::Recovered => 0x2ecc71,
+        EventKind::Digest => 0x95a5a6,
+    };
+    let mut details = event.details();
+    // the time is shown by the timestamp of the embed
+    details.pop();
+    let fields = details
+        .into_iter()
+        .map(|(label, value)| {
+            let mut value = if value.is_empty() { "-".to_owned() } else { value };
+            if value.chars().count() > MAX_FIELD_VALUE {
+                value = value.chars().take(MAX_FIELD_VALUE - 3).collect::<String>() + "...";
+            }
+            json!({
+                "name": label,
+                "value": value,
+                "inline": !value.contains('\n'),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "embeds": [{
+            "title": event.title(),
+            "color": color,
+            "fields": fields,
+            "timestamp": event.timestamp().to_rfc3339(),
+            "footer": {"text": "ddns-rs"},
+        }],
+    })
+}
+
+#[async_trait(?Send)]
+impl Notifier for Discord {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut message = build_message(event);
+        if let Some(username) = &self.username {
+            message["username"] = json!(username);
+        }
+        if let Some(avatar_url) = &self.avatar_url {
+            message["avatar_url"] = json!(avatar_url);
+        }
+        post_json(&self.client, &self.webhook_url, &message).await?;
+        Ok(())
+    }
+}
@@ -1,69 +1,129 @@
-use std::net::IpAddr;
-
-use anyhow::Result;
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
-use lettre::message::{header, MultiPart, SinglePart};
+use lettre::message::{header, Mailbox, MultiPart, SinglePart};
 use lettre::transport::smtp::authentication::{Credentials, Mechanism};
 use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
 use maud::html;
+use serde::{Deserialize, Serialize};
 
+use super::template::{TemplateContext, Templates};
+use crate::event::Event;
 use crate::notifiers::Notifier;
 
+const SUBJECT_TEMPLATE: &str = "subject";
+const HTML_TEMPLATE: &str = "body.html";
+const TEXT_TEMPLATE: &str = "body.txt";
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tls {
+    /// TLS from the start of the connection (SMTPS), default port is 465
+    Implicit,
+    /// Upgrade a plain connection with STARTTLS, default port is 587
+    Starttls,
+    /// No encryption at all, default port is 25, only for trusted relays
+    None,
+}
+
 pub struct Email {
     mailer: AsyncSmtpTransport<Tokio1Executor>,
-    subject: String,
-    to: String,
-    from: String,
+    templates: Templates,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    cc: Vec<Mailbox>,
+    bcc: Vec<Mailbox>,
+}
+
+fn parse_mailboxes<S: AsRef<str>>(name: &str, addresses: Vec<S>) -> Result<Vec<Mailbox>> {
+    addresses
+        .iter()
+        .map(|v| {
+            v.as_ref()
+                .parse()
+                .map_err(|err| anyhow!("{} address '{}' illegal: {}", name, v.as_ref(), err))
+        })
+        .collect()
 }
 
 impl Email {
     #[allow(clippy::too_many_arguments)]
     pub async fn create<S: AsRef<str>>(
-        smtp_username: S,
-        smtp_password: S,
+        smtp_username: Option<S>,
+        smtp_password: Option<S>,
         smtp_host: S,
         smtp_port: Option<u16>,
-        smtp_starttls: bool,
+        smtp_tls: Tls,
         subject: Option<S>,
+        html_template: Option<S>,
+        text_template: Option<S>,
         from: Option<S>,
-        to: S,
+        to: Vec<S>,
+        cc: Vec<S>,
+        bcc: Vec<S>,
     ) -> Result<Email> {
-        let smtp_username = smtp_username.as_ref().to_owned();
-        let smtp_password = smtp_password.as_ref().to_owned();
-        let smtp_host = smtp_host.as_ref().to_owned();
-        let mut mailer_builder = if smtp_starttls {
-            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp_host)?
-        } else {
-            AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp_host)?
+        let smtp_host = smtp_host.as_ref();
+        let mut mailer_builder = match smtp_tls {
+            Tls::Implicit => AsyncSmtpTransport::<Tokio1Executor>::relay(smtp_host)?,
+            Tls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(smtp_host)?,
+            Tls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(smtp_host),
         };
         if let Some(smtp_port) = smtp_port {
             mailer_builder = mailer_builder.port(smtp_port)
         }
-        mailer_builder = mailer_builder
-            .authentication(vec![Mechanism::Plain, Mechanism::Login, Mechanism::Xoauth2])
-            .credentials(Credentials::from((&smtp_username, &smtp_password)));
-        let mailer = mailer_builder.build();
-        let subject = match subject {
-            None => "DDNS-RS Notification".to_owned(),
-            Some(subject) => subject.as_ref().to_owned(),
-        };
-        let from = match from {
-            None => {
-                format!("ddns-rs <{}>", &smtp_username)
+        // relays which accept mails without authentication don't need credentials
+        let from = match (&smtp_username, smtp_password) {
+            (Some(smtp_username), Some(smtp_password)) => {
+                mailer_builder = mailer_builder
+                    .authentication(vec![Mechanism::Plain, Mechanism::Login, Mechanism::Xoauth2])
+                    .credentials(Credentials::new(
+                        smtp_username.as_ref().to_owned(),
+                        smtp_password.as_ref().to_owned(),
+                    ));
+                match from {
+                    None => format!("ddns-rs <{}>", smtp_username.as_ref()),
+                    Some(from) => from.as_ref().to_owned(),
+                }
             },
-            Some(from) => from.as_ref().to_owned(),
+            (None, None) => from.ok_or(anyhow!("missing from arg"))?.as_ref().to_owned(),
+            _ => bail!("smtp_username and smtp_password must be specified together"),
         };
-        let to = to.as_ref().to_owned();
+        let mailer = mailer_builder.build();
+        let from = from
+            .parse()
+            .map_err(|err| anyhow!("from address '{}' illegal: {}", from, err))?;
+        let to = parse_mailboxes("to", to)?;
+        let cc = parse_mailboxes("cc", cc)?;
+        let bcc = parse_mailboxes("bcc", bcc)?;
+        if to.is_empty() && cc.is_empty() && bcc.is_empty() {
+            bail!("email has no recipient");
+        }
+        let mut templates = Templates::new();
+        if let Some(subject) = subject {
+            templates.add(SUBJECT_TEMPLATE, subject.as_ref())?;
+        }
+        if let Some(html_template) = html_template {
+            templates.add(HTML_TEMPLATE, html_template.as_ref())?;
+        }
+        if let Some(text_template) = text_template {
+            templates.add(TEXT_TEMPLATE, text_template.as_ref())?;
+        }
         Ok(Email {
             mailer,
-            subject,
+            templates,
             from,
             to,
+            cc,
+            bcc,
         })
     }
 }
 
-fn build_email(new_ips: &[IpAddr]) -> String {
+fn build_email(event: &Event) -> String {
+    let new_ips = match event {
+        Event::Changed(event) => Some(&event.new_ips),
+        _ => None,
+    };
     // Create the html we want to send.
     let html = html! {
         head {
@@ -138,6 +198,17 @@ fn build_email(new_ips: &[IpAddr]) -> String {
                     font-weight: 500;
                     letter-spacing: 1px;
                     padding: 10px;
+                }
+
+                .detail {
+                    margin: 20px 20px 0 20px;
+                    color: #2a3439;
+                    border-collapse: collapse;
+                }
+
+                .detail th {
+                    text-align: left;
+                    padding: 2px 15px 2px 0;
                 }"
             }
         }
@@ -147,10 +218,27 @@ fn build_email(new_ips: &[IpAddr]) -> String {
                     span class="title-text" { "DDNS-RS" }
                 }
             }
-            ol class="ip-box" {
-                @for ip in new_ips.iter() {
-                    li class="ip-item" {
-                        (ip)
+            table class="detail" {
+                @for (label, value) in event.details() {
+                    // the new addresses of the change event are listed below
+                    @if !(new_ips.is_some() && label == "New IP List") {
+                        tr {
+                            th { (label) }
+                            td {
+                                @for line in value.lines() {
+                                    (line) br;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            @if let Some(new_ips) = new_ips {
+                ol class="ip-box" {
+                    @for ip in new_ips.iter() {
+                        li class="ip-item" {
+                            (ip)
+                        }
                     }
                 }
             }
@@ -159,42 +247,50 @@ fn build_email(new_ips: &[IpAddr]) -> String {
     html.into_string()
 }
 
-fn build_email_plaintext(new_ips: &[IpAddr]) -> String {
-    let new_ips_str = new_ips
-        .iter()
-        .map(|v| format!("\t{}\n", v))
-        .collect::<Vec<_>>()
-        .concat();
+fn build_email_plaintext(event: &Event) -> String {
     let logo = r#"
 ┌┬┐┌┬┐┌┐┌┌─┐   ┬─┐┌─┐
  ││ │││││└─┐───├┬┘└─┐
 ─┴┘─┴┘┘└┘└─┘   ┴└─└─┘
 DNS record updater
 "#;
-    format!("{}New IP List:\n{}", logo, new_ips_str)
+    format!("{}{}\n", logo, event.text())
 }
 
 #[async_trait(?Send)]
 impl Notifier for Email {
-    async fn send(&self, new_ips: &[IpAddr]) -> Result<()> {
-        let email = Message::builder()
-            .from(self.from.parse().unwrap())
-            .to(self.to.parse().unwrap())
-            .subject(&self.subject)
-            .multipart(
-                MultiPart::alternative()
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_PLAIN)
-                            .body(build_email_plaintext(new_ips)),
-                    )
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(header::ContentType::TEXT_HTML)
-                            .body(build_email(new_ips)),
-                    ),
-            )
-            .unwrap();
+    async fn send(&self, event: &Event) -> Result<()> {
+        let context = TemplateContext::new(event);
+        let subject = if self.templates.contains(SUBJECT_TEMPLATE) {
+            self.templates.render(SUBJECT_TEMPLATE, &context)?
+        } else {
+            format!("DDNS-RS Notification: {}", event.title())
+        };
+        let html = if self.templates.contains(HTML_TEMPLATE) {
+            self.templates.render(HTML_TEMPLATE, &context)?
+        } else {
+            build_email(event)
+        };
+        let text = if self.templates.contains(TEXT_TEMPLATE) {
+            self.templates.render(TEXT_TEMPLATE, &context)?
+        } else {
+            build_email_plaintext(event)
+        };
+        let mut builder = Message::builder().from(self.from.clone()).subject(subject);
+        for to in &self.to {
+            builder = builder.to(to.clone());
+        }
+        for cc in &self.cc {
+            builder = builder.cc(cc.clone());
+        }
+        for bcc in &self.bcc {
+            builder = builder.bcc(bcc.clone());
+        }
+        let email = builder.multipart(
+            MultiPart::alternative()
+                .singlepart(SinglePart::builder().header(header::ContentType::TEXT_PLAIN).body(text))
+                .singlepart(SinglePart::builder().header(header::ContentType::TEXT_HTML).body(html)),
+        )?;
 
         self.mailer.send(email).await?;
         Ok(())
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use log::{debug, warn};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::time::timeout;
+
+use crate::event::Event;
+use crate::Notifier;
+
+/// Run a command with the details of the event
+pub struct Exec {
+    program: String,
+    arguments: Vec<String>,
+    env: HashMap<String, String>,
+    working_directory: Option<PathBuf>,
+    stdin: bool,
+    timeout: Duration,
+    success_codes: Vec<i32>,
+}
+
+impl Exec {
+    pub async fn create<S: AsRef<str>>(
+        command: Vec<S>,
+        env: Vec<(S, S)>,
+        working_directory: Option<S>,
+        stdin: bool,
+        timeout: Duration,
+        success_codes: Vec<i32>,
+    ) -> Result<Exec> {
+        let mut command = command.iter().map(|v| v.as_ref().to_owned());
+        let program = command.next().ok_or_else(|| anyhow!("command can't be empty"))?;
+        Ok(Exec {
+            program,
+            arguments: command.collect(),
+            env: env
+                .iter()
+                .map(|(k, v)| (k.as_ref().to_owned(), v.as_ref().to_owned()))
+                .collect(),
+            working_directory: working_directory.map(|v| PathBuf::from(v.as_ref())),
+            stdin,
+            timeout,
+            success_codes,
+        })
+    }
+}
+
+/// The environment variables describing the event, all of them start with `DDNS_`
+fn build_env(event: &Event) -> Vec<(&'static str, String)> {
+    let join = |ips: &[std::net::IpAddr]| ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+    let mut env = vec![
+        ("DDNS_EVENT", event.kind().to_string()),
+        ("DDNS_TASK", event.task().to_owned()),
+        ("DDNS_TITLE", event.title()),
+        ("DDNS_TIMESTAMP", event.timestamp().to_rfc3339()),
+    ];
+    match event {
+        Event::Changed(event) => {
+            env.push(("DDNS_PROVIDER", event.provider.clone()));
+            env.push(("DDNS_DOMAIN", event.domain.clone().unwrap_or_default()));
+            env.push(("DDNS_FAMILY", event.family.to_string().to_lowercase()));
+            env.push(("DDNS_OLD_IPS", join(&event.old_ips)));
+            env.push(("DDNS_NEW_IPS", join(&event.new_ips)));
+            env.push(("DDNS_HOSTNAME", event.hostname.clone()));
+        },
+        Event::Failed(event) | Event::FailedRepeatedly(event) => {
+            env.push(("DDNS_PROVIDER", event.provider.clone()));
+            env.push(("DDNS_DOMAIN", event.domain.clone().unwrap_or_default()));
+            env.push(("DDNS_ERROR", event.error.clone()));
+            env.push(("DDNS_FAILURES", event.failures.to_string()));
+            env.push(("DDNS_HOSTNAME", event.hostname.clone()));
+        },
+        Event::Recovered(event) => {
+            env.push(("DDNS_PROVIDER", event.provider.clone()));
+            env.push(("DDNS_DOMAIN", event.domain.clone().unwrap_or_default()));
+            env.push(("DDNS_FAILURES", event.failures.to_string()));
+            env.push(("DDNS_HOSTNAME", event.hostname.clone()));
+        },
+        Event::Digest(event) => {
+            // the events themselves are only available from stdin
+            env.push(("DDNS_EVENT_COUNT", event.events.len().to_string()));
+            env.push(("DDNS_HOSTNAME", event.hostname.clone()));
+        },
+    }
+    env
+}
+
+#[async_trait(?Send)]
+impl Notifier for Exec {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.arguments)
+            .envs(&self.env)
+            .envs(build_env(event))
+            .stdin(if self.stdin { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            // the child is killed if it times out
+            .kill_on_drop(true);
+        if let Some(working_directory) = &self.working_directory {
+            command.current_dir(working_directory);
+        }
+        let mut child = command
+            .spawn()
+            .map_err(|err| anyhow!("can't run {}: {}", self.program, err))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            let json = serde_json::to_vec(event)?;
+            // the command may exit without reading the input, that is not an error
+            if let Err(err) = stdin.write_all(&json).await {
+                warn!("can't write event to the stdin of {}: {}", self.program, err);
+            }
+        }
+        let output = timeout(self.timeout, child.wait_with_output())
+            .await
+            .map_err(|_| anyhow!("{} timed out after {}s", self.program, self.timeout.as_secs()))??;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if !stdout.trim().is_empty() {
+            debug!("{} stdout: {}", self.program, stdout.trim());
+        }
+        match output.status.code() {
+            Some(code) if self.success_codes.contains(&code) => Ok(()),
+            Some(code) => bail!("{} exited with code {}: {}", self.program, code, stderr.trim()),
+            None => bail!("{} terminated by signal: {}", self.program, stderr.trim()),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use base64::prelude::*;
+use chrono::Utc;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::hmac_sha256;
+use crate::event::Event;
+use crate::Notifier;
+
+/// Feishu and its international version Lark
+pub struct Feishu {
+    webhook_url: String,
+    secret: Option<String>,
+    client: Client,
+}
+
+impl Feishu {
+    pub async fn create<S: AsRef<str>>(webhook_url: S, secret: Option<S>) -> Result<Feishu> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(Feishu {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            secret: secret.map(|v| v.as_ref().to_owned()),
+            client,
+        })
+    }
+}
+
+/// Unlike DingTalk, the string to sign is used as the key and the message is empty,
+/// see <https://open.feishu.cn/document/client-docs/bot-v3/add-custom-bot#3c6592d6>
+fn sign(secret: &str, timestamp: i64) -> String {
+    let string_to_sign = format!("{}\n{}", timestamp, secret);
+    BASE64_STANDARD.encode(hmac_sha256(string_to_sign.as_bytes(), b""))
+}
+
+/// Rich text message, every detail is a paragraph
+fn build_message(event: &Event) -> Value {
+    let content = event
+        .details()
+        .into_iter()
+        .map(|(label, value)| {
+            json!([
+                {"tag": "text", "text": format!("{}: ", label)},
+                {"tag": "text", "text": value.replace('\n', ", ")},
+            ])
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "msg_type": "post",
+        "content": {
+            "post": {
+                "zh_cn": {
+                    "title": event.title(),
+                    "content": content,
+                },
+            },
+        },
+    })
+}
+
+#[async_trait(?Send)]
+impl Notifier for Feishu {
+    async fn send(&self, event: &Event) -> Result<()> {
+        #[derive(Deserialize)]
+        struct BotResponse {
+            #[serde(alias = "StatusCode")]
+            code: i64,
+            #[serde(alias = "StatusMessage", default)]
+            msg: String,
+        }
+
+        let mut message = build_message(event);
+        if let Some(secret) = &self.secret {
+            let timestamp = Utc::now().timestamp();
+            message["timestamp"] = json!(timestamp.to_string());
+            message["sign"] = json!(sign(secret, timestamp));
+        }
+        let response = self.client.post(&self.webhook_url).json(&message).send().await?;
+        let status = response.status();
+        let result = response.json::<BotResponse>().await?;
+        if result.code != 0 {
+            bail!("{status}: {} {}", result.code, result.msg)
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+
+use super::send_request;
+use crate::event::Event;
+use crate::Notifier;
+
+pub struct Gotify {
+    url: String,
+    app_token: String,
+    priority: Option<i64>,
+    client: Client,
+}
+
+impl Gotify {
+    pub async fn create<S: AsRef<str>>(server: S, app_token: S, priority: Option<i64>) -> Result<Gotify> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(Gotify {
+            url: format!("{}/message", server.as_ref().trim_end_matches('/')),
+            app_token: app_token.as_ref().to_owned(),
+            priority,
+            client,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Notifier for Gotify {
+    async fn send(&self, event: &Event) -> Result<()> {
+        /// See <https://gotify.net/api-docs#/message/createMessage>
+        #[derive(Serialize)]
+        struct MessageParams {
+            title: String,
+            message: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            priority: Option<i64>,
+        }
+
+        let params = MessageParams {
+            title: event.title(),
+            message: event.text(),
+            priority: self.priority,
+        };
+        let request = self
+            .client
+            .post(&self.url)
+            .header("X-Gotify-Key", &self.app_token)
+            .json(&params);
+        send_request(request).await?;
+        Ok(())
+    }
+}
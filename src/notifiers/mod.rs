@@ -1,14 +1,92 @@
-use std::net::IpAddr;
-
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_trait::async_trait;
-pub use email::Email;
-pub use webhook::Webhook;
+pub use bark::Bark;
+pub use dingtalk::DingTalk;
+pub use discord::Discord;
+pub use email::{Email, Tls as EmailTls};
+pub use exec::Exec;
+pub use feishu::Feishu;
+use futures::future;
+pub use gotify::Gotify;
+use hmac::{Hmac, Mac};
+pub use mqtt::Mqtt;
+pub use ntfy::{parse_priority as parse_ntfy_priority, Auth as NtfyAuth, Ntfy};
+pub use outbox::Outbox;
+pub use pushover::Pushover;
+use reqwest::{Client, RequestBuilder};
+use serde::Serialize;
+use sha2::Sha256;
+pub use slack::Slack;
+pub use teams::Teams;
+pub use telegram::{ParseMode as TelegramParseMode, Telegram};
+pub use throttle::Throttle;
+pub use webhook::{Signing as WebhookSigning, Webhook};
+pub use wecom::WeCom;
+
+use crate::event::Event;
 
+mod bark;
+mod dingtalk;
+mod discord;
 mod email;
+mod exec;
+mod feishu;
+mod gotify;
+mod mqtt;
+mod ntfy;
+mod outbox;
+mod pushover;
+mod slack;
+mod teams;
+mod telegram;
+mod template;
+mod throttle;
 mod webhook;
+mod wecom;
 
 #[async_trait(?Send)]
 pub trait Notifier {
-    async fn send(&self, new_ips: &[IpAddr]) -> Result<()>;
+    async fn send(&self, event: &Event) -> Result<()>;
+
+    /// The work of the notifier besides sending, e.g. sending the held back events, it never finishes by default
+    async fn background(&self) {
+        future::pending::<()>().await
+    }
+
+    /// Wait for the delivery in progress of the background work, nothing is delivered by it afterwards, so it can be
+    /// dropped without sending an event twice
+    async fn stop(&self) {}
+
+    /// Send the held back or queued events right away instead of waiting for the background work, used before exiting
+    /// without running it
+    async fn drain(&self) {}
+}
+
+/// The host name of this machine, used to tell where the notification comes from
+pub(crate) fn hostname() -> String {
+    gethostname::gethostname().to_string_lossy().into_owned()
+}
+
+/// Post the JSON body and fail if the status is not success, returns the response body
+pub(crate) async fn post_json<T: Serialize + ?Sized>(client: &Client, url: &str, body: &T) -> Result<String> {
+    send_request(client.post(url).json(body)).await
+}
+
+/// Send the request and fail if the status is not success, the error carries the response body
+pub(crate) async fn send_request(request: RequestBuilder) -> Result<String> {
+    let response = request.send().await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        bail!("{status}: {text}")
+    }
+    Ok(text)
+}
+
+/// HMAC-SHA256 of the message, used to sign the requests
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> Vec<u8> {
+    // HMAC accepts keys of any length, so this never fails
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC can take key of any size");
+    mac.update(message);
+    mac.finalize().into_bytes().to_vec()
 }
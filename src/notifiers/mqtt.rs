@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use log::debug;
+use rumqttc::{AsyncClient, Event as MqttEvent, Incoming, MqttOptions, Outgoing, QoS, TlsConfiguration, Transport};
+use serde_json::json;
+use tokio::time::timeout;
+
+use super::hostname;
+use crate::event::{ChangeEvent, Event};
+use crate::Notifier;
+
+const TOPIC: &str = "ddns-rs/events";
+const DISCOVERY_PREFIX: &str = "homeassistant";
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct HomeAssistant {
+    pub discovery_prefix: String,
+}
+
+pub struct Mqtt {
+    options: MqttOptions,
+    topic: String,
+    qos: QoS,
+    retain: bool,
+    home_assistant: Option<HomeAssistant>,
+}
+
+impl Mqtt {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create<S: AsRef<str>>(
+        host: S,
+        port: Option<u16>,
+        tls: bool,
+        client_id: Option<S>,
+        username: Option<S>,
+        password: Option<S>,
+        topic: Option<S>,
+        qos: u8,
+        retain: bool,
+        home_assistant: bool,
+        discovery_prefix: Option<S>,
+    ) -> Result<Mqtt> {
+        let qos = match qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            _ => bail!("qos must be 0, 1 or 2"),
+        };
+        let port = port.unwrap_or(if tls { 8883 } else { 1883 });
+        let client_id = match client_id {
+            None => format!("ddns-rs-{}", hostname()),
+            Some(client_id) => client_id.as_ref().to_owned(),
+        };
+        let mut options = MqttOptions::new(client_id, host.as_ref(), port);
+        options.set_keep_alive(Duration::from_secs(30));
+        match (username, password) {
+            (Some(username), Some(password)) => {
+                options.set_credentials(username.as_ref(), password.as_ref());
+            },
+            (None, None) => {},
+            _ => bail!("username and password must be set together"),
+        }
+        if tls {
+            options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+        }
+        let topic = match topic {
+            None => TOPIC.to_owned(),
+            Some(topic) => topic.as_ref().to_owned(),
+        };
+        let home_assistant = if home_assistant {
+            Some(HomeAssistant {
+                discovery_prefix: match discovery_prefix {
+                    None => DISCOVERY_PREFIX.to_owned(),
+                    Some(discovery_prefix) => discovery_prefix.as_ref().to_owned(),
+                },
+            })
+        } else {
+            None
+        };
+        Ok(Mqtt {
+            options,
+            topic,
+            qos,
+            retain,
+            home_assistant,
+        })
+    }
+
+    /// Connect, publish all messages, wait for the acknowledgements and disconnect
+    async fn publish(&self, messages: Vec<(String, QoS, bool, String)>) -> Result<()> {
+        let (client, mut event_loop) = AsyncClient::new(self.options.clone(), messages.len() + 1);
+        // messages are sent without acknowledgement for QoS 0
+        let mut pending = messages.len();
+        for (topic, qos, retain, payload) in messages {
+            client.publish(topic, qos, retain, payload).await?;
+        }
+        let mut disconnecting = false;
+        loop {
+            match event_loop.poll().await? {
+                MqttEvent::Outgoing(Outgoing::Publish(_)) if !disconnecting => {
+                    if self.qos == QoS::AtMostOnce {
+                        pending = pending.saturating_sub(1);
+                    }
+                },
+                MqttEvent::Incoming(Incoming::PubAck(_)) | MqttEvent::Incoming(Incoming::PubComp(_)) => {
+                    pending = pending.saturating_sub(1);
+                },
+                MqttEvent::Outgoing(Outgoing::Disconnect) => {
+                    return Ok(());
+                },
+                event => {
+                    debug!("mqtt event: {:?}", event);
+                },
+            }
+            if pending == 0 && !disconnecting {
+                disconnecting = true;
+                client.disconnect().await?;
+            }
+        }
+    }
+}
+
+/// Only alphanumerics and underscore are allowed in the object id of Home Assistant
+fn object_id(event: &ChangeEvent) -> String {
+    let id = format!("ddns_rs_{}_{}", event.task, event.family)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>();
+    id
+}
+
+#[async_trait(?Send)]
+impl Notifier for Mqtt {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut messages = vec![(self.topic.clone(), self.qos, self.retain, serde_json::to_string(event)?)];
+        // the changes held back in a digest still update the sensors
+        let changes = match event {
+            Event::Changed(event) => vec![event],
+            Event::Digest(event) => event
+                .events
+                .iter()
+                .filter_map(|v| match v {
+                    Event::Changed(event) => Some(event),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        for event in changes {
+            let Some(home_assistant) = &self.home_assistant else {
+                break;
+            };
+            // the state of the sensor, see <https://www.home-assistant.io/integrations/mqtt/#mqtt-discovery>
+            let object_id = object_id(event);
+            let family = event.family.to_string().to_lowercase();
+            let state_topic = format!("{}/{}/{}/state", self.topic, event.task, family);
+            let config = json!({
+                "name": format!("{} {}", event.target(), event.family),
+                "unique_id": object_id,
+                "object_id": object_id,
+                "state_topic": state_topic,
+                "icon": "mdi:ip-network",
+                "device": {
+                    "identifiers": [format!("ddns_rs_{}", event.hostname)],
+                    "name": format!("ddns-rs on {}", event.hostname),
+                    "manufacturer": "ddns-rs",
+                },
+            });
+            let config_topic = format!("{}/sensor/{}/config", home_assistant.discovery_prefix, object_id);
+            messages.push((config_topic, self.qos, true, config.to_string()));
+            let state = event
+                .new_ips
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            messages.push((state_topic, self.qos, true, state));
+        }
+        timeout(SEND_TIMEOUT, self.publish(messages))
+            .await
+            .map_err(|_| anyhow!("publishing to mqtt broker timed out"))?
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Serialize;
+
+use super::send_request;
+use crate::event::Event;
+use crate::Notifier;
+
+const SERVER: &str = "https://ntfy.sh";
+
+pub enum Auth {
+    None,
+    Token(String),
+    Basic(String, String),
+}
+
+pub struct Ntfy {
+    server: String,
+    topic: String,
+    priority: Option<u8>,
+    tags: Vec<String>,
+    auth: Auth,
+    client: Client,
+}
+
+impl Ntfy {
+    pub async fn create<S: AsRef<str>>(
+        server: Option<S>,
+        topic: S,
+        priority: Option<u8>,
+        tags: Vec<S>,
+        auth: Auth,
+    ) -> Result<Ntfy> {
+        if let Some(priority) = priority {
+            if !(1..=5).contains(&priority) {
+                bail!("ntfy priority must between 1 and 5")
+            }
+        }
+        let server = match &server {
+            None => SERVER,
+            Some(server) => server.as_ref(),
+        };
+        let client = reqwest::Client::builder().build()?;
+        Ok(Ntfy {
+            server: server.trim_end_matches('/').to_owned(),
+            topic: topic.as_ref().to_owned(),
+            priority,
+            tags: tags.iter().map(|v| v.as_ref().to_owned()).collect(),
+            auth,
+            client,
+        })
+    }
+}
+
+/// Parse the priority name of ntfy, see <https://docs.ntfy.sh/publish/#message-priority>
+pub fn parse_priority(priority: &str) -> Result<u8> {
+    let priority = match priority {
+        "min" => 1,
+        "low" => 2,
+        "default" => 3,
+        "high" => 4,
+        "max" | "urgent" => 5,
+        _ => bail!("unknown ntfy priority {}", priority),
+    };
+    Ok(priority)
+}
+
+#[async_trait(?Send)]
+impl Notifier for Ntfy {
+    async fn send(&self, event: &Event) -> Result<()> {
+        /// See <https://docs.ntfy.sh/publish/#publish-as-json>
+        #[derive(Serialize)]
+        struct PublishParams<'a> {
+            topic: &'a str,
+            title: String,
+            message: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            priority: Option<u8>,
+            #[serde(skip_serializing_if = "<[_]>::is_empty")]
+            tags: &'a [String],
+        }
+
+        let params = PublishParams {
+            topic: &self.topic,
+            title: event.title(),
+            message: event.text(),
+            priority: self.priority,
+            tags: &self.tags,
+        };
+        let mut request = self.client.post(&self.server).json(&params);
+        request = match &self.auth {
+            Auth::None => request,
+            Auth::Token(token) => request.bearer_auth(token),
+            Auth::Basic(username, password) => request.basic_auth(username, Some(password)),
+        };
+        send_request(request).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,370 @@
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use futures::future::join;
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tokio::select;
+use tokio::sync::{Mutex, Notify};
+use tokio::time::sleep;
+
+use crate::event::Event;
+use crate::Notifier;
+
+/// An event waiting for delivery, one file for each
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    /// The number of failed deliveries
+    attempts: u32,
+    next_attempt: DateTime<Local>,
+    event: Event,
+}
+
+/// Wrap a notifier to keep the events on disk until they are delivered
+///
+/// `send` only writes the event to the directory, the background worker delivers the events in order and retries the
+/// failed one with exponential backoff. The events left by the last run are delivered after starting.
+pub struct Outbox {
+    inner: Box<dyn Notifier>,
+    directory: PathBuf,
+    retry_interval: Duration,
+    max_retry_interval: Duration,
+    max_age: Duration,
+    /// Tells the worker there is a new event
+    wakeup: Notify,
+    /// Keep the file names unique and ordered when events arrive in the same nanosecond
+    sequence: Cell<u32>,
+    /// Held while an entry is being delivered
+    delivering: Mutex<()>,
+    stopped: Cell<bool>,
+}
+
+impl Outbox {
+    /// The events of each notifier are kept in a subdirectory named after it
+    pub async fn create<P: AsRef<Path>>(
+        inner: Box<dyn Notifier>,
+        directory: P,
+        name: &str,
+        retry_interval: Duration,
+        max_retry_interval: Duration,
+        max_age: Duration,
+    ) -> Result<Outbox> {
+        let name = name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect::<String>();
+        let directory = directory.as_ref().join(name);
+        fs::create_dir_all(&directory)
+            .await
+            .map_err(|err| anyhow!("can't create outbox directory {}: {}", directory.display(), err))?;
+        Ok(Outbox {
+            inner,
+            directory,
+            retry_interval,
+            max_retry_interval,
+            max_age,
+            wakeup: Notify::new(),
+            sequence: Cell::new(0),
+            delivering: Mutex::new(()),
+            stopped: Cell::new(false),
+        })
+    }
+
+    /// Where the events of this notifier are kept
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The file names of the waiting events, oldest first
+    async fn entries(&self) -> Result<Vec<PathBuf>> {
+        let mut entries = vec![];
+        let mut dir = fs::read_dir(&self.directory).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|v| v == "json") {
+                entries.push(path);
+            }
+        }
+        entries.sort();
+        Ok(entries)
+    }
+
+    async fn write(&self, path: &Path, entry: &Entry) -> Result<()> {
+        // write to a temporary file first, so a crash never leaves a broken entry
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, serde_json::to_vec(entry)?).await?;
+        fs::rename(&temp, path).await?;
+        Ok(())
+    }
+
+    /// Deliver the due events, returns how long to wait for the next retry if one failed
+    async fn deliver(&self) -> Result<Option<Duration>> {
+        for path in self.entries().await? {
+            let _delivering = self.delivering.lock().await;
+            if self.stopped.get() {
+                return Ok(None);
+            }
+            let mut entry: Entry = match serde_json::from_slice(&fs::read(&path).await?) {
+                Ok(v) => v,
+                Err(err) => {
+                    warn!("drop broken outbox entry {}: {}", path.display(), err);
+                    fs::remove_file(&path).await?;
+                    continue;
+                },
+            };
+            let now = Local::now();
+            let age = (now - entry.event.timestamp()).to_std().unwrap_or_default();
+            if !self.max_age.is_zero() && age > self.max_age {
+                warn!(
+                    "drop {} notification of task '{}' after {} attempt(s), it's too old",
+                    entry.event.kind(),
+                    entry.event.task(),
+                    entry.attempts
+                );
+                fs::remove_file(&path).await?;
+                continue;
+            }
+            if entry.next_attempt > now {
+                return Ok(Some((entry.next_attempt - now).to_std().unwrap_or_default()));
+            }
+            match self.inner.send(&entry.event).await {
+                Ok(()) => {
+                    debug!("delivered outbox entry {}", path.display());
+                    fs::remove_file(&path).await?;
+                },
+                Err(err) => {
+                    entry.attempts += 1;
+                    let delay = self
+                        .retry_interval
+                        .saturating_mul(2u32.saturating_pow(entry.attempts - 1))
+                        .min(self.max_retry_interval);
+                    entry.next_attempt = Local::now() + delay;
+                    warn!(
+                        "can't send {} notification of task '{}', retry in {}s: {err:#?}",
+                        entry.event.kind(),
+                        entry.event.task(),
+                        delay.as_secs()
+                    );
+                    self.write(&path, &entry).await?;
+                    // keep the order, the later events wait for this one
+                    return Ok(Some(delay));
+                },
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[async_trait(?Send)]
+impl Notifier for Outbox {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let timestamp = Local::now();
+        let sequence = self.sequence.get();
+        self.sequence.set(sequence.wrapping_add(1));
+        let name = format!(
+            "{:020}-{:010}.json",
+            timestamp.timestamp_nanos_opt().unwrap_or_default(),
+            sequence
+        );
+        let entry = Entry {
+            attempts: 0,
+            next_attempt: timestamp,
+            event: event.clone(),
+        };
+        self.write(&self.directory.join(name), &entry).await?;
+        self.wakeup.notify_one();
+        Ok(())
+    }
+
+    async fn background(&self) {
+        let worker = async {
+            match self.entries().await {
+                Ok(entries) if !entries.is_empty() => {
+                    info!("{} notification(s) left in {}", entries.len(), self.directory.display());
+                },
+                _ => {},
+            }
+            loop {
+                let wait = match self.deliver().await {
+                    Ok(wait) => wait,
+                    Err(err) => {
+                        warn!("can't read outbox {}: {err:#?}", self.directory.display());
+                        Some(self.retry_interval)
+                    },
+                };
+                match wait {
+                    Some(wait) => {
+                        select! {
+                            _ = sleep(wait) => {},
+                            _ = self.wakeup.notified() => {},
+                        }
+                    },
+                    None => self.wakeup.notified().await,
+                }
+            }
+        };
+        join(self.inner.background(), worker).await;
+    }
+
+    async fn stop(&self) {
+        self.stopped.set(true);
+        let _ = self.delivering.lock().await;
+        self.inner.stop().await;
+    }
+
+    async fn drain(&self) {
+        // the events which still can't be delivered are left for the next run
+        if let Err(err) = self.deliver().await {
+            warn!("can't read outbox {}: {err:#?}", self.directory.display());
+        }
+        self.inner.drain().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use anyhow::bail;
+
+    use super::*;
+    use crate::event::FailureEvent;
+
+    /// Records the tasks of the sent events, fails while `failing` is set
+    #[derive(Clone, Default)]
+    struct Recorder {
+        sent: Rc<RefCell<Vec<String>>>,
+        failing: Rc<Cell<bool>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Notifier for Recorder {
+        async fn send(&self, event: &Event) -> Result<()> {
+            if self.failing.get() {
+                bail!("unavailable");
+            }
+            self.sent.borrow_mut().push(event.task().to_owned());
+            Ok(())
+        }
+    }
+
+    fn event(task: &str, timestamp: DateTime<Local>) -> Event {
+        Event::Failed(FailureEvent {
+            task: task.to_owned(),
+            provider: "p1".to_owned(),
+            domain: None,
+            error: "error".to_owned(),
+            failures: 1,
+            since: timestamp,
+            timestamp,
+            hostname: "host".to_owned(),
+        })
+    }
+
+    async fn outbox(directory: &Path, recorder: &Recorder, max_age: Duration) -> Outbox {
+        Outbox::create(
+            Box::new(recorder.clone()),
+            directory,
+            "n1",
+            Duration::from_secs(10),
+            Duration::from_secs(25),
+            max_age,
+        )
+        .await
+        .unwrap()
+    }
+
+    async fn read_entry(path: &Path) -> Entry {
+        serde_json::from_slice(&fs::read(path).await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn entries_are_ordered() {
+        let directory = tempfile::tempdir().unwrap();
+        let outbox = outbox(directory.path(), &Recorder::default(), Duration::ZERO).await;
+        for task in ["a", "b", "c"] {
+            outbox.send(&event(task, Local::now())).await.unwrap();
+        }
+        let entries = outbox.entries().await.unwrap();
+        let mut tasks = vec![];
+        for path in &entries {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let (nanos, sequence) = name.split_once('-').unwrap();
+            assert_eq!((nanos.len(), sequence.len()), (20, 10), "{name}");
+            tasks.push(read_entry(path).await.event.task().to_owned());
+        }
+        assert_eq!(tasks, ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn deliver_in_order() {
+        let directory = tempfile::tempdir().unwrap();
+        let recorder = Recorder::default();
+        let outbox = outbox(directory.path(), &recorder, Duration::ZERO).await;
+        for task in ["a", "b", "c"] {
+            outbox.send(&event(task, Local::now())).await.unwrap();
+        }
+        assert!(recorder.sent.borrow().is_empty());
+        assert_eq!(outbox.deliver().await.unwrap(), None);
+        assert_eq!(*recorder.sent.borrow(), ["a", "b", "c"]);
+        assert!(outbox.entries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff() {
+        let directory = tempfile::tempdir().unwrap();
+        let recorder = Recorder::default();
+        let outbox = outbox(directory.path(), &recorder, Duration::ZERO).await;
+        outbox.send(&event("a", Local::now())).await.unwrap();
+        outbox.send(&event("b", Local::now())).await.unwrap();
+        recorder.failing.set(true);
+
+        // the interval doubles on each failure up to the max retry interval, the later events wait
+        for (attempts, delay) in [(1, 10), (2, 20), (3, 25)] {
+            let wait = outbox.deliver().await.unwrap();
+            assert_eq!(wait, Some(Duration::from_secs(delay)));
+            let entries = outbox.entries().await.unwrap();
+            assert_eq!(entries.len(), 2);
+            let mut entry = read_entry(&entries[0]).await;
+            assert_eq!(entry.attempts, attempts);
+            assert!(entry.next_attempt > Local::now());
+            // it's not retried before the time
+            let wait = outbox.deliver().await.unwrap().unwrap();
+            assert!(wait <= Duration::from_secs(delay));
+            entry.next_attempt = Local::now();
+            outbox.write(&entries[0], &entry).await.unwrap();
+        }
+
+        recorder.failing.set(false);
+        assert_eq!(outbox.deliver().await.unwrap(), None);
+        assert_eq!(*recorder.sent.borrow(), ["a", "b"]);
+        assert!(outbox.entries().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn drop_expired() {
+        let directory = tempfile::tempdir().unwrap();
+        let recorder = Recorder::default();
+        let outbox = outbox(directory.path(), &recorder, Duration::from_secs(60)).await;
+        outbox
+            .send(&event("old", Local::now() - chrono::Duration::minutes(5)))
+            .await
+            .unwrap();
+        outbox.send(&event("new", Local::now())).await.unwrap();
+        assert_eq!(outbox.deliver().await.unwrap(), None);
+        assert_eq!(*recorder.sent.borrow(), ["new"]);
+        assert!(outbox.entries().await.unwrap().is_empty());
+    }
+}
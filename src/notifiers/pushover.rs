@@ -0,0 +1,90 @@
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use super::post_json;
+use crate::event::Event;
+use crate::Notifier;
+
+const API_ENDPOINT: &str = "https://api.pushover.net/1/messages.json";
+
+pub struct Pushover {
+    user_key: String,
+    app_token: String,
+    device: Option<String>,
+    sound: Option<String>,
+    priority: Option<i8>,
+    client: Client,
+}
+
+impl Pushover {
+    pub async fn create<S: AsRef<str>>(
+        user_key: S,
+        app_token: S,
+        device: Option<S>,
+        sound: Option<S>,
+        priority: Option<i8>,
+    ) -> Result<Pushover> {
+        if let Some(priority) = priority {
+            // emergency priority (2) requires retry and expire, which is not supported
+            if !(-2..=1).contains(&priority) {
+                bail!("pushover priority must between -2 and 1")
+            }
+        }
+        let client = reqwest::Client::builder().build()?;
+        Ok(Pushover {
+            user_key: user_key.as_ref().to_owned(),
+            app_token: app_token.as_ref().to_owned(),
+            device: device.map(|v| v.as_ref().to_owned()),
+            sound: sound.map(|v| v.as_ref().to_owned()),
+            priority,
+            client,
+        })
+    }
+}
+
+#[async_trait(?Send)]
+impl Notifier for Pushover {
+    async fn send(&self, event: &Event) -> Result<()> {
+        /// See <https://pushover.net/api#messages>
+        #[derive(Serialize)]
+        struct MessageParams<'a> {
+            token: &'a str,
+            user: &'a str,
+            title: String,
+            message: String,
+            timestamp: i64,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            device: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            sound: Option<&'a str>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            priority: Option<i8>,
+        }
+
+        #[derive(Deserialize)]
+        struct MessageResponse {
+            status: i32,
+            #[serde(default)]
+            errors: Vec<String>,
+        }
+
+        let params = MessageParams {
+            token: &self.app_token,
+            user: &self.user_key,
+            title: event.title(),
+            message: event.text(),
+            timestamp: event.timestamp().timestamp(),
+            device: self.device.as_deref(),
+            sound: self.sound.as_deref(),
+            priority: self.priority,
+        };
+        let response = post_json(&self.client, API_ENDPOINT, &params).await?;
+        let result = serde_json::from_str::<MessageResponse>(&response)?;
+        if result.status != 1 {
+            bail!("{}", result.errors.join(", "))
+        }
+        Ok(())
+    }
+}
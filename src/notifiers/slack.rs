@@ -0,0 +1,91 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::post_json;
+use crate::event::Event;
+use crate::Notifier;
+
+// Slack allows at most 10 fields in a section block
+const MAX_SECTION_FIELDS: usize = 10;
+
+pub struct Slack {
+    webhook_url: String,
+    channel: Option<String>,
+    username: Option<String>,
+    icon_emoji: Option<String>,
+    client: Client,
+}
+
+impl Slack {
+    pub async fn create<S: AsRef<str>>(
+        webhook_url: S,
+        channel: Option<S>,
+        username: Option<S>,
+        icon_emoji: Option<S>,
+    ) -> Result<Slack> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(Slack {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            channel: channel.map(|v| v.as_ref().to_owned()),
+            username: username.map(|v| v.as_ref().to_owned()),
+            icon_emoji: icon_emoji.map(|v| v.as_ref().to_owned()),
+            client,
+        })
+    }
+}
+
+/// See <https://api.slack.com/reference/block-kit/blocks>
+fn build_message(event: &Event) -> Value {
+    let mut details = event.details();
+    let time = details.pop().map(|(_, time)| time).unwrap_or_default();
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": {"type": "plain_text", "text": event.title()},
+    })];
+    for chunk in details.chunks(MAX_SECTION_FIELDS) {
+        let fields = chunk
+            .iter()
+            .map(|(label, value)| {
+                json!({
+                    "type": "mrkdwn",
+                    "text": format!("*{}*\n{}", label, escape(value)),
+                })
+            })
+            .collect::<Vec<_>>();
+        blocks.push(json!({"type": "section", "fields": fields}));
+    }
+    blocks.push(json!({
+        "type": "context",
+        "elements": [{"type": "mrkdwn", "text": format!("ddns-rs at {}", escape(&time))}],
+    }));
+    json!({
+        // used by the notification of the desktop and the mobile
+        "text": event.title(),
+        "blocks": blocks,
+    })
+}
+
+/// See <https://api.slack.com/reference/surfaces/formatting#escaping>
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[async_trait(?Send)]
+impl Notifier for Slack {
+    async fn send(&self, event: &Event) -> Result<()> {
+        let mut message = build_message(event);
+        if let Some(channel) = &self.channel {
+            message["channel"] = json!(channel);
+        }
+        if let Some(username) = &self.username {
+            message["username"] = json!(username);
+        }
+        if let Some(icon_emoji) = &self.icon_emoji {
+            message["icon_emoji"] = json!(icon_emoji);
+        }
+        post_json(&self.client, &self.webhook_url, &message).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::{json, Value};
+
+use super::post_json;
+use crate::event::{Event, EventKind};
+use crate::Notifier;
+
+pub struct Teams {
+    webhook_url: String,
+    client: Client,
+}
+
+impl Teams {
+    pub async fn create<S: AsRef<str>>(webhook_url: S) -> Result<Teams> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(Teams {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            client,
+        })
+    }
+}
+
+/// See <https://learn.microsoft.com/en-us/microsoftteams/platform/webhooks-and-connectors/how-to/connectors-using>
+fn build_message(event: &Event) -> Value {
+    let color = match event.kind() {
+        EventKind::Changed => "Accent",
+        EventKind::Failed => "Warning",
+        EventKind::FailedRepeatedly => "Attention",
+        EventKind::Recovered => "Good",
+        EventKind::Digest => "Default",
+    };
+    let facts = event
+        .details()
+        .into_iter()
+        .map(|(label, value)| {
+            json!({
+                "title": label,
+                // adaptive cards need an empty line to break the line
+                "value": value.replace('\n', "\n\n"),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "type": "message",
+        "attachments": [{
+            "contentType": "application/vnd.microsoft.card.adaptive",
+            "contentUrl": null,
+            "content": {
+                "$schema": "http://adaptivecards.io/schemas/adaptive-card.json",
+                "type": "AdaptiveCard",
+                "version": "1.4",
+                "body": [
+                    {
+                        "type": "TextBlock",
+                        "text": event.title(),
+                        "weight": "Bolder",
+                        "size": "Medium",
+                        "color": color,
+                        "wrap": true,
+                    },
+                    {
+                        "type": "FactSet",
+                        "facts": facts,
+                    },
+                ],
+            },
+        }],
+    })
+}
+
+#[async_trait(?Send)]
+impl Notifier for Teams {
+    async fn send(&self, event: &Event) -> Result<()> {
+        post_json(&self.client, &self.webhook_url, &build_message(event)).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use std::fmt::Write;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::event::Event;
+use crate::Notifier;
+
+const API_BASE_URL: &str = "https://api.telegram.org";
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseMode {
+    Plain,
+    #[serde(alias = "MarkdownV2")]
+    MarkdownV2,
+    #[serde(alias = "HTML")]
+    Html,
+}
+
+pub struct Telegram {
+    url: String,
+    chat_id: String,
+    thread_id: Option<i64>,
+    parse_mode: ParseMode,
+    silent: bool,
+    client: Client,
+}
+
+impl Telegram {
+    pub async fn create<S: AsRef<str>>(
+        bot_token: S,
+        chat_id: S,
+        thread_id: Option<i64>,
+        parse_mode: ParseMode,
+        silent: bool,
+        api_base_url: Option<S>,
+    ) -> Result<Telegram> {
+        let api_base_url = match &api_base_url {
+            None => API_BASE_URL,
+            Some(api_base_url) => api_base_url.as_ref(),
+        };
+        let url = format!(
+            "{}/bot{}/sendMessage",
+            api_base_url.trim_end_matches('/'),
+            bot_token.as_ref()
+        );
+        let client = reqwest::Client::builder().build()?;
+        Ok(Telegram {
+            url,
+            chat_id: chat_id.as_ref().to_owned(),
+            thread_id,
+            parse_mode,
+            silent,
+            client,
+        })
+    }
+}
+
+fn build_message(event: &Event, parse_mode: ParseMode) -> String {
+    let mut message = String::new();
+    match parse_mode {
+        ParseMode::Plain => {
+            let _ = write!(message, "{}\n\n{}", event.title(), event.text());
+        },
+        ParseMode::MarkdownV2 => {
+            let _ = writeln!(message, "*{}*\n", escape_markdown(&event.title()));
+            for (label, value) in event.details() {
+                let _ = writeln!(message, "*{}:* {}", escape_markdown(label), escape_markdown(&value));
+            }
+        },
+        ParseMode::Html => {
+            let _ = writeln!(message, "<b>{}</b>\n", escape_html(&event.title()));
+            for (label, value) in event.details() {
+                let _ = writeln!(message, "<b>{}:</b> {}", escape_html(label), escape_html(&value));
+            }
+        },
+    }
+    message
+}
+
+/// See <https://core.telegram.org/bots/api#markdownv2-style>
+fn escape_markdown(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[async_trait(?Send)]
+impl Notifier for Telegram {
+    async fn send(&self, event: &Event) -> Result<()> {
+        #[derive(Serialize)]
+        struct SendMessageParams<'a> {
+            chat_id: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            message_thread_id: Option<i64>,
+            text: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            parse_mode: Option<&'a str>,
+            disable_notification: bool,
+        }
+
+        #[derive(Deserialize)]
+        struct SendMessageResponse {
+            ok: bool,
+            description: Option<String>,
+        }
+
+        let params = SendMessageParams {
+            chat_id: &self.chat_id,
+            message_thread_id: self.thread_id,
+            text: build_message(event, self.parse_mode),
+            parse_mode: match self.parse_mode {
+                ParseMode::Plain => None,
+                ParseMode::MarkdownV2 => Some("MarkdownV2"),
+                ParseMode::Html => Some("HTML"),
+            },
+            disable_notification: self.silent,
+        };
+        let response = self.client.post(&self.url).json(&params).send().await?;
+        let status = response.status();
+        let result = response
+            .json::<SendMessageResponse>()
+            .await
+            .map_err(|err| anyhow!("{status}: can't parse telegram response: {err}"))?;
+        if !result.ok {
+            bail!("{status}: {}", result.description.unwrap_or_default())
+        }
+        Ok(())
+    }
+}
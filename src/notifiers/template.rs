@@ -0,0 +1,70 @@
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Result};
+use minijinja::Environment;
+use serde::Serialize;
+
+use crate::event::Event;
+
+/// The variables can be used in the templates, all fields of the event plus the created or updated addresses split by
+/// family
+#[derive(Serialize)]
+pub struct TemplateContext<'a> {
+    #[serde(flatten)]
+    pub event: &'a Event,
+    pub title: String,
+    pub text: String,
+    pub ipv4_list: Vec<&'a IpAddr>,
+    pub ipv6_list: Vec<&'a IpAddr>,
+}
+
+impl<'a> TemplateContext<'a> {
+    pub fn new(event: &'a Event) -> TemplateContext<'a> {
+        // the lists keep their meaning from before the events, only the addresses written to the records
+        let updated_ips = match event {
+            Event::Changed(event) => event.changes.iter().filter_map(|v| v.new.as_ref()).collect(),
+            _ => vec![],
+        };
+        TemplateContext {
+            event,
+            title: event.title(),
+            text: event.text(),
+            ipv4_list: updated_ips.iter().copied().filter(|v| v.is_ipv4()).collect(),
+            ipv6_list: updated_ips.iter().copied().filter(|v| v.is_ipv6()).collect(),
+        }
+    }
+}
+
+/// A set of named templates compiled at startup, so syntax errors are reported before anything is sent
+///
+/// Templates use the jinja syntax, see <https://docs.rs/minijinja/latest/minijinja/syntax/index.html>.
+/// Auto escaping is decided by the extension of the name, e.g. `body.html` escapes HTML while `body` escapes nothing.
+pub struct Templates {
+    env: Environment<'static>,
+}
+
+impl Templates {
+    pub fn new() -> Templates {
+        Templates {
+            env: Environment::new(),
+        }
+    }
+
+    pub fn add<N: Into<String>, S: Into<String>>(&mut self, name: N, source: S) -> Result<()> {
+        let name = name.into();
+        self.env
+            .add_template_owned(name.clone(), source.into())
+            .map_err(|err| anyhow!("template '{}' illegal: {:#}", name, err))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.env.get_template(name).is_ok()
+    }
+
+    pub fn render<C: Serialize>(&self, name: &str, context: C) -> Result<String> {
+        let template = self.env.get_template(name)?;
+        template
+            .render(context)
+            .map_err(|err| anyhow!("can't render template '{}': {:#}", name, err))
+    }
+}
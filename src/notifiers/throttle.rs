@@ -0,0 +1,247 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Local};
+use futures::future::join;
+use log::{debug, info, warn};
+use tokio::fs;
+use tokio::sync::Mutex;
+use tokio::time::{interval_at, Instant};
+
+use crate::event::{DigestEvent, Event};
+use crate::Notifier;
+
+/// Wrap a notifier to drop duplicated events, limit the rate and send the held back events as a digest
+pub struct Throttle {
+    inner: Box<dyn Notifier>,
+    dedup_window: Duration,
+    rate_limit: usize,
+    rate_limit_period: Duration,
+    digest_interval: Duration,
+    /// The last accepted event of each slot and the time of it
+    recent: RefCell<HashMap<String, (String, Instant)>>,
+    /// The start of the failure of each task whose failed event was dropped, the events ending it are dropped too
+    muted: RefCell<HashMap<String, DateTime<Local>>>,
+    /// The time of the notifications sent in the last `rate_limit_period`
+    sent: RefCell<VecDeque<Instant>>,
+    /// The events waiting for the next digest
+    pending: RefCell<Vec<Event>>,
+    /// The file keeping the pending events across restarts and reloads
+    state: Option<PathBuf>,
+    /// Held while the pending events are being sent
+    flushing: Mutex<()>,
+    stopped: Cell<bool>,
+}
+
+impl Throttle {
+    /// The pending events left in `state` by the last run are loaded
+    pub async fn create(
+        inner: Box<dyn Notifier>,
+        dedup_window: Duration,
+        rate_limit: u32,
+        rate_limit_period: Duration,
+        digest_interval: Duration,
+        state: Option<PathBuf>,
+    ) -> Result<Throttle> {
+        if rate_limit > 0 && rate_limit_period.is_zero() {
+            bail!("rate_limit_period can't be zero");
+        }
+        let mut pending = vec![];
+        if let Some(state) = &state {
+            match fs::read(state).await {
+                Ok(contents) => match serde_json::from_slice::<Vec<Event>>(&contents) {
+                    Ok(events) => {
+                        if !events.is_empty() {
+                            info!("{} held back notification(s) left in {}", events.len(), state.display());
+                        }
+                        pending = events;
+                    },
+                    Err(err) => warn!("drop broken held back notifications {}: {}", state.display(), err),
+                },
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {},
+                Err(err) => bail!("can't read {}: {}", state.display(), err),
+            }
+        }
+        Ok(Throttle {
+            inner,
+            dedup_window,
+            rate_limit: rate_limit as usize,
+            rate_limit_period,
+            digest_interval,
+            recent: RefCell::new(HashMap::new()),
+            muted: RefCell::new(HashMap::new()),
+            sent: RefCell::new(VecDeque::new()),
+            pending: RefCell::new(pending),
+            state,
+            flushing: Mutex::new(()),
+            stopped: Cell::new(false),
+        })
+    }
+
+    /// Write the pending events to the state file if there is one
+    async fn save(&self) -> Result<()> {
+        let Some(state) = &self.state else {
+            return Ok(());
+        };
+        let contents = serde_json::to_vec(&*self.pending.borrow())?;
+        // write to a temporary file first, so a crash never leaves a broken state
+        let temp = state.with_extension("tmp");
+        fs::write(&temp, contents).await?;
+        fs::rename(&temp, state)
+            .await
+            .map_err(|err| anyhow!("can't write {}: {}", state.display(), err))?;
+        Ok(())
+    }
+
+    /// Hold back the event until the next flush
+    async fn hold(&self, event: &Event) -> Result<()> {
+        self.pending.borrow_mut().push(event.clone());
+        self.save().await
+    }
+
+    /// Whether the event equals the last one accepted in its slot within the dedup window, the event is remembered if
+    /// not
+    fn is_duplicated(&self, event: &Event) -> bool {
+        if self.dedup_window.is_zero() {
+            return false;
+        }
+        // the failure is reported or not as a whole
+        match event {
+            Event::FailedRepeatedly(event) if self.muted.borrow().get(&event.task) == Some(&event.since) => {
+                return true;
+            },
+            Event::Recovered(event) if self.muted.borrow().get(&event.task) == Some(&event.since) => {
+                self.muted.borrow_mut().remove(&event.task);
+                return true;
+            },
+            _ => {},
+        }
+        let Some((slot, value)) = dedup_key(event) else {
+            return false;
+        };
+        let now = Instant::now();
+        let mut recent = self.recent.borrow_mut();
+        recent.retain(|_, (_, time)| now.duration_since(*time) < self.dedup_window);
+        if recent.get(&slot).is_some_and(|(last, _)| *last == value) {
+            if let Event::Failed(event) = event {
+                self.muted.borrow_mut().insert(event.task.clone(), event.since);
+            }
+            return true;
+        }
+        recent.insert(slot, (value, now));
+        false
+    }
+
+    /// Take a place of the rate limit, false if there is none left
+    fn acquire(&self) -> bool {
+        if self.rate_limit == 0 {
+            return true;
+        }
+        let now = Instant::now();
+        let mut sent = self.sent.borrow_mut();
+        while sent
+            .front()
+            .is_some_and(|v| now.duration_since(*v) >= self.rate_limit_period)
+        {
+            sent.pop_front();
+        }
+        if sent.len() >= self.rate_limit {
+            return false;
+        }
+        sent.push_back(now);
+        true
+    }
+
+    /// Send the pending events, as is if there is only one, `force` ignores the rate limit
+    async fn flush(&self, force: bool) {
+        let _flushing = self.flushing.lock().await;
+        if self.stopped.get() || self.pending.borrow().is_empty() || (!force && !self.acquire()) {
+            return;
+        }
+        // the events stay pending until they are sent, a crash may send them twice but never loses them
+        let events = self.pending.borrow().clone();
+        let count = events.len();
+        let event = if count == 1 {
+            events.into_iter().next().unwrap()
+        } else {
+            Event::Digest(DigestEvent::new(events))
+        };
+        debug!("sending held back event(s): {}", event.title());
+        if let Err(err) = self.inner.send(&event).await {
+            warn!("can't send held back event(s), retry later: {err:#?}");
+            return;
+        }
+        // the events arrived during sending are left
+        self.pending.borrow_mut().drain(..count);
+        if let Err(err) = self.save().await {
+            warn!("can't save held back event(s): {err:#?}");
+        }
+    }
+}
+
+/// The slot of the event and the value compared with the last one of the slot, e.g. the address changed to the same
+/// value again, the events ending a failure follow the failed event
+fn dedup_key(event: &Event) -> Option<(String, String)> {
+    match event {
+        Event::Changed(event) => {
+            let mut ips = event.new_ips.clone();
+            ips.sort();
+            let ips = ips.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(",");
+            Some((format!("changed/{}/{}", event.task, event.family), ips))
+        },
+        Event::Failed(event) => Some((format!("failed/{}", event.task), event.error.clone())),
+        _ => None,
+    }
+}
+
+#[async_trait(?Send)]
+impl Notifier for Throttle {
+    async fn send(&self, event: &Event) -> Result<()> {
+        if self.is_duplicated(event) {
+            info!("{} notification is dropped, it has been sent recently", event.kind());
+            return Ok(());
+        }
+        if !self.digest_interval.is_zero() {
+            return self.hold(event).await;
+        }
+        if !self.acquire() {
+            info!("{} notification is held back by rate limit", event.kind());
+            return self.hold(event).await;
+        }
+        self.inner.send(event).await
+    }
+
+    async fn background(&self) {
+        // held back events are sent in every digest interval, or every rate limit period
+        let period = if !self.digest_interval.is_zero() {
+            self.digest_interval
+        } else if self.rate_limit > 0 {
+            self.rate_limit_period
+        } else {
+            return self.inner.background().await;
+        };
+        let flush = async {
+            let mut timer = interval_at(Instant::now() + period, period);
+            loop {
+                timer.tick().await;
+                self.flush(false).await;
+            }
+        };
+        join(self.inner.background(), flush).await;
+    }
+
+    async fn stop(&self) {
+        self.stopped.set(true);
+        let _ = self.flushing.lock().await;
+        self.inner.stop().await;
+    }
+
+    async fn drain(&self) {
+        self.flush(true).await;
+        self.inner.drain().await;
+    }
+}
@@ -1,26 +1,95 @@
+use std::fmt::Write;
 use std::net::IpAddr;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use log::debug;
+use reqwest::header::HeaderName;
+use reqwest::{Client, Method};
 use serde_json::json;
+use sha2::{Digest, Sha256};
 
+use super::hmac_sha256;
+use super::template::{TemplateContext, Templates};
+use crate::event::Event;
 use crate::Notifier;
 
+const URL_TEMPLATE: &str = "url";
+const BODY_TEMPLATE: &str = "body";
+
+/// Sign the requests like GitHub and Stripe webhooks
+///
+/// The signature is `sha256=<hex of HMAC-SHA256 of "<timestamp>.<body>">`, the timestamp is the unix time of sending,
+/// the delivery id is derived from the event so it stays the same when the event is sent again.
+pub struct Signing {
+    pub secret: String,
+    pub signature_header: String,
+    pub timestamp_header: String,
+    pub delivery_id_header: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, v| {
+        let _ = write!(hex, "{:02x}", v);
+        hex
+    })
+}
+
 pub struct Webhook {
-    url: String,
-    authorization_header: String,
+    method: Method,
+    headers: Vec<(String, String)>,
+    content_type: String,
+    authorization_header: Option<String>,
+    signing: Option<Signing>,
+    templates: Templates,
     client: Client,
 }
 
 impl Webhook {
+    #[allow(clippy::too_many_arguments)]
     pub async fn create<S: AsRef<str>>(
         url: S,
-        authorization_header: S,
+        method: Option<S>,
+        headers: Vec<(S, S)>,
+        content_type: Option<S>,
+        body: Option<S>,
+        authorization_header: Option<S>,
+        signing: Option<Signing>,
         local_address: Option<IpAddr>,
     ) -> Result<Webhook> {
-        let url = url.as_ref().to_owned();
-        let authorization_header = authorization_header.as_ref().to_owned();
+        let method = match method {
+            None => Method::POST,
+            Some(method) => Method::from_bytes(method.as_ref().to_uppercase().as_bytes())
+                .map_err(|_| anyhow!("unknown http method {}", method.as_ref()))?,
+        };
+        let mut templates = Templates::new();
+        templates.add(URL_TEMPLATE, url.as_ref())?;
+        if let Some(body) = body {
+            templates.add(BODY_TEMPLATE, body.as_ref())?;
+        }
+        let check_header_name =
+            |name: &str| HeaderName::from_bytes(name.as_bytes()).map_err(|_| anyhow!("illegal header name {}", name));
+        if let Some(signing) = &signing {
+            check_header_name(&signing.signature_header)?;
+            check_header_name(&signing.timestamp_header)?;
+            check_header_name(&signing.delivery_id_header)?;
+        }
+        let mut header_names = vec![];
+        for (i, (name, value)) in headers.iter().enumerate() {
+            check_header_name(name.as_ref())?;
+            header_names.push(name.as_ref().to_owned());
+            templates.add(format!("header.{i}"), value.as_ref())?;
+        }
+        let headers = header_names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| (name, format!("header.{i}")))
+            .collect();
+        let content_type = match content_type {
+            None => "application/json".to_owned(),
+            Some(content_type) => content_type.as_ref().to_owned(),
+        };
+        let authorization_header = authorization_header.map(|v| v.as_ref().to_owned());
         let builder = reqwest::Client::builder();
         let client = if let Some(local_address) = local_address {
             builder.local_address(local_address).build()?
@@ -28,8 +97,12 @@ impl Webhook {
             builder.build()?
         };
         Ok(Webhook {
-            url,
+            method,
+            headers,
+            content_type,
             authorization_header,
+            signing,
+            templates,
             client,
         })
     }
@@ -37,21 +110,46 @@ impl Webhook {
 
 #[async_trait(?Send)]
 impl Notifier for Webhook {
-    async fn send(&self, new_ips: &[IpAddr]) -> anyhow::Result<()> {
-        let url = &self.url;
-        let ipv4_list = new_ips.iter().filter(|v| v.is_ipv4()).collect::<Vec<_>>();
-        let ipv6_list = new_ips.iter().filter(|v| v.is_ipv6()).collect::<Vec<_>>();
-        let json = vec![json!({
-            "ipv4_list": ipv4_list,
-            "ipv6_list": ipv6_list,
-        })];
-
-        self.client
-            .post(url)
-            .header(reqwest::header::AUTHORIZATION, &self.authorization_header)
-            .json(&json)
-            .send()
-            .await?;
+    async fn send(&self, event: &Event) -> anyhow::Result<()> {
+        let context = TemplateContext::new(event);
+        let url = self.templates.render(URL_TEMPLATE, &context)?;
+        let body = if self.templates.contains(BODY_TEMPLATE) {
+            self.templates.render(BODY_TEMPLATE, &context)?
+        } else if let Event::Changed(_) = event {
+            // like before the events, nothing is sent if only records are deleted
+            if context.ipv4_list.is_empty() && context.ipv6_list.is_empty() {
+                debug!("skip {} notification without created or updated address", event.kind());
+                return Ok(());
+            }
+            serde_json::to_string(&vec![json!({
+                "ipv4_list": context.ipv4_list,
+                "ipv6_list": context.ipv6_list,
+            })])?
+        } else {
+            serde_json::to_string(event)?
+        };
+
+        let mut request = self
+            .client
+            .request(self.method.clone(), url)
+            .header(reqwest::header::CONTENT_TYPE, &self.content_type);
+        if let Some(authorization_header) = &self.authorization_header {
+            request = request.header(reqwest::header::AUTHORIZATION, authorization_header);
+        }
+        for (name, template) in &self.headers {
+            request = request.header(name, self.templates.render(template, &context)?);
+        }
+        if let Some(signing) = &self.signing {
+            let timestamp = chrono::Utc::now().timestamp().to_string();
+            let message = format!("{}.{}", timestamp, body);
+            let signature = hex(&hmac_sha256(signing.secret.as_bytes(), message.as_bytes()));
+            let delivery_id = hex(&Sha256::digest(serde_json::to_vec(event)?)[..16]);
+            request = request
+                .header(&signing.signature_header, format!("sha256={}", signature))
+                .header(&signing.timestamp_header, timestamp)
+                .header(&signing.delivery_id_header, delivery_id);
+        }
+        request.body(body).send().await?.error_for_status()?;
         Ok(())
     }
 }
@@ -0,0 +1,61 @@
+use std::fmt::Write;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::event::Event;
+use crate::Notifier;
+
+/// WeCom (WeChat Work) group bot
+pub struct WeCom {
+    webhook_url: String,
+    client: Client,
+}
+
+impl WeCom {
+    pub async fn create<S: AsRef<str>>(webhook_url: S) -> Result<WeCom> {
+        let client = reqwest::Client::builder().build()?;
+        Ok(WeCom {
+            webhook_url: webhook_url.as_ref().to_owned(),
+            client,
+        })
+    }
+}
+
+/// See <https://developer.work.weixin.qq.com/document/path/91770#markdown%E7%B1%BB%E5%9E%8B>
+fn build_markdown(event: &Event) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "### {}", event.title());
+    for (label, value) in event.details() {
+        let _ = writeln!(text, "> **{}**: {}", label, value.replace('\n', ", "));
+    }
+    text
+}
+
+#[async_trait(?Send)]
+impl Notifier for WeCom {
+    async fn send(&self, event: &Event) -> Result<()> {
+        #[derive(Deserialize)]
+        struct BotResponse {
+            errcode: i64,
+            errmsg: String,
+        }
+
+        let message = json!({
+            "msgtype": "markdown",
+            "markdown": {
+                "content": build_markdown(event),
+            },
+        });
+        let response = self.client.post(&self.webhook_url).json(&message).send().await?;
+        let status = response.status();
+        let result = response.json::<BotResponse>().await?;
+        if result.errcode != 0 {
+            bail!("{status}: {} {}", result.errcode, result.errmsg)
+        }
+        Ok(())
+    }
+}
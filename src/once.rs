@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use chrono::Local;
+
+use crate::event::{Event, FailureEvent};
+use crate::factory::{build_interfaces, build_notifiers, build_providers};
+use crate::setting::Setting;
+use crate::{notifiers, notify, run_task, Shutdown};
+
+/// Run the tasks once and print a summary, all the tasks if no name is given, returns whether all of them succeeded
+///
+/// Only the interfaces, providers and notifiers used by the tasks are built.
+pub(crate) async fn run_once(shutdown: Arc<Shutdown>, setting: Setting, names: Vec<String>) -> bool {
+    let mut names = if names.is_empty() {
+        setting.tasks.keys().cloned().collect()
+    } else {
+        names
+    };
+    names.sort();
+    names.dedup();
+    if let Some(name) = names.iter().find(|v| !setting.tasks.contains_key(*v)) {
+        println!("error: can't find task '{name}'");
+        return false;
+    }
+    let (succeeded, failed) = match run(shutdown, setting, &names).await {
+        Ok(v) => v,
+        Err(err) => {
+            println!("error: {err:#}");
+            return false;
+        },
+    };
+    println!("{succeeded} task(s) succeeded, {failed} failed");
+    failed == 0
+}
+
+async fn run(shutdown: Arc<Shutdown>, setting: Setting, names: &[String]) -> Result<(usize, usize)> {
+    let tasks = names
+        .iter()
+        .map(|name| (name, &setting.tasks[name]))
+        .collect::<Vec<_>>();
+    let used_notifiers = setting
+        .notifiers
+        .iter()
+        .filter(|(name, _)| tasks.iter().any(|(_, task)| task.notifiers.contains(name)))
+        .map(|(name, v)| (name.clone(), v.clone()))
+        .collect();
+    let used_providers = setting
+        .providers
+        .iter()
+        .filter(|(name, _)| tasks.iter().any(|(_, task)| &task.provider == *name))
+        .map(|(name, v)| (name.clone(), v.clone()))
+        .collect();
+    let used_interfaces = setting.interfaces_with_members(tasks.iter().map(|(_, task)| &*task.interface));
+    let interface_map = build_interfaces(used_interfaces, HashMap::new()).await?;
+    let notifier_map = build_notifiers(&setting.base, used_notifiers).await?;
+    let provider_map = build_providers(shutdown, used_providers).await?;
+
+    let (mut succeeded, mut failed) = (0, 0);
+    for (name, task) in tasks {
+        let notifiers = task
+            .notifiers
+            .iter()
+            .map(|v| notifier_map[v].clone())
+            .collect::<Vec<_>>();
+        let (provider, ttl, force) = provider_map[&task.provider].clone();
+        let domain = provider.domain().map(|v| v.to_owned());
+        let interface = interface_map
+            .get(&task.interface)
+            .ok_or_else(|| anyhow!("can't find interface define"))?
+            .clone();
+        let result = run_task(
+            name,
+            task.families()?,
+            (&task.provider, provider, ttl, force),
+            interface,
+            &notifiers,
+        )
+        .await;
+        match result {
+            Ok(changes) if changes.is_empty() => {
+                println!("task '{name}': up to date");
+                succeeded += 1;
+            },
+            Ok(changes) => {
+                for change in changes {
+                    let changes = change.changes.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                    println!(
+                        "task '{name}': {} {} updated, {}",
+                        change.family,
+                        change.target(),
+                        changes.join(", ")
+                    );
+                }
+                succeeded += 1;
+            },
+            Err(err) => {
+                println!("task '{name}': failed, {err:#}");
+                failed += 1;
+                let event = Event::Failed(FailureEvent {
+                    task: name.clone(),
+                    provider: task.provider.clone(),
+                    domain,
+                    error: format!("{err:#}"),
+                    failures: 1,
+                    since: Local::now(),
+                    timestamp: Local::now(),
+                    hostname: notifiers::hostname(),
+                });
+                notify(&notifiers, &event).await;
+            },
+        }
+    }
+
+    // the background work of the notifiers isn't running, send what they hold back before exiting
+    for (notifier, _) in notifier_map.values() {
+        if let Some(notifier) = &**notifier {
+            notifier.drain().await;
+        }
+    }
+    Ok((succeeded, failed))
+}
@@ -155,6 +155,10 @@ impl Cloudflare {
 impl Provider for Cloudflare {
     type DNSRecord = DNSRecord;
 
+    fn domain(&self) -> Option<&str> {
+        Some(&self.dns)
+    }
+
     async fn get_dns_record(&self, family: IpType) -> Result<Vec<Self::DNSRecord>> {
         #[derive(Serialize)]
         #[serde(rename_all = "lowercase")]
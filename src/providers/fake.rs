@@ -49,6 +49,7 @@ impl AsRef<IpAddr> for DNSRecord {
 }
 
 pub struct Fake {
+    dns: Option<String>,
     id_index: AtomicU32,
     ipv4_cache: Arc<Mutex<HashMap<u32, DNSRecord>>>,
     ipv6_cache: Arc<Mutex<HashMap<u32, DNSRecord>>>,
@@ -56,11 +57,12 @@ pub struct Fake {
 }
 
 impl Fake {
-    pub async fn create(shutdown: Arc<Shutdown>) -> Result<Self> {
+    pub async fn create<D: AsRef<str>>(shutdown: Arc<Shutdown>, dns: Option<D>) -> Result<Self> {
         let ipv4_cache = Arc::new(Mutex::new(HashMap::with_capacity(10)));
         let ipv6_cache = Arc::new(Mutex::new(HashMap::with_capacity(10)));
         let (tx, mut rx) = mpsc::channel::<DNSRecord>(10);
         let result = Fake {
+            dns: dns.map(|v| v.as_ref().to_owned()),
             id_index: AtomicU32::new(1),
             ipv4_cache: ipv4_cache.clone(),
             ipv6_cache: ipv6_cache.clone(),
@@ -108,6 +110,10 @@ impl Fake {
 impl Provider for Fake {
     type DNSRecord = DNSRecord;
 
+    fn domain(&self) -> Option<&str> {
+        self.dns.as_deref()
+    }
+
     async fn get_dns_record(&self, family: IpType) -> Result<Vec<Self::DNSRecord>> {
         match family {
             IpType::V4 => {
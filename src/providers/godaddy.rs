@@ -40,6 +40,7 @@ impl AsRef<IpAddr> for DNSRecord {
 }
 
 pub struct Godaddy {
+    dns: String,
     domain: String,
     name: String,
     client: Client,
@@ -61,6 +62,7 @@ impl Godaddy {
         let name = dns.trim_end_matches(&domain).trim_end_matches('.').to_owned();
 
         Ok(Godaddy {
+            dns,
             domain,
             name,
             client,
@@ -73,6 +75,10 @@ impl Godaddy {
 impl Provider for Godaddy {
     type DNSRecord = DNSRecord;
 
+    fn domain(&self) -> Option<&str> {
+        Some(&self.dns)
+    }
+
     async fn get_dns_record(&self, family: IpType) -> Result<Vec<Self::DNSRecord>> {
         let mut records = vec![];
         let kind = match family {
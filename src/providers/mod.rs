@@ -1,11 +1,13 @@
 use std::cmp::Ordering;
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::net::IpAddr;
 
 use anyhow::Result;
 use async_trait::async_trait;
 use log::info;
+use serde::{Deserialize, Serialize};
 
 pub use self::cloudflare::Cloudflare;
 pub use self::fake::Fake;
@@ -20,6 +22,9 @@ mod godaddy;
 pub trait Provider {
     type DNSRecord: AsRef<IpAddr> + Eq + PartialEq;
 
+    /// The name of the record managed by this provider
+    fn domain(&self) -> Option<&str>;
+
     async fn get_dns_record(&self, family: IpType) -> Result<Vec<Self::DNSRecord>>;
     async fn create_dns_record(&self, ip: &IpAddr, ttl: u32) -> Result<()>;
     async fn update_dns_record(&self, record: &Self::DNSRecord, ip: &IpAddr) -> Result<()>;
@@ -52,9 +57,72 @@ impl<T: Provider> PartialEq<Self> for HashSetItem<'_, T> {
 
 impl<T: Provider> Eq for HashSetItem<'_, T> {}
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+    ForceUpdate,
+}
+
+impl Display for Operation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+            Operation::ForceUpdate => "force update",
+        };
+        write!(f, "{}", str)
+    }
+}
+
+/// A single operation applied to the remote records
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub operation: Operation,
+    pub old: Option<IpAddr>,
+    pub new: Option<IpAddr>,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) if old != new => write!(f, "{} {} -> {}", self.operation, old, new),
+            (_, Some(new)) => write!(f, "{} {}", self.operation, new),
+            (Some(old), None) => write!(f, "{} {}", self.operation, old),
+            (None, None) => write!(f, "{}", self.operation),
+        }
+    }
+}
+
+/// What `check_and_update` found on the remote and what it did
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    /// The addresses of the remote records before updating
+    pub old_ips: Vec<IpAddr>,
+    pub changes: Vec<Change>,
+}
+
+impl ChangeSet {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
 #[async_trait(?Send)]
 pub(crate) trait DynProvider {
-    async fn check_and_update(&self, new_ips: &[IpAddr], ttl: u32, force: bool, family: IpType) -> Result<Vec<IpAddr>>;
+    fn domain(&self) -> Option<&str>;
+
+    /// The addresses of the remote records
+    async fn get_ips(&self, family: IpType) -> Result<Vec<IpAddr>>;
+
+    /// Delete the records of the address, returns whether there was any
+    async fn delete_ip(&self, ip: &IpAddr) -> Result<bool>;
+
+    async fn check_and_update(&self, new_ips: &[IpAddr], ttl: u32, force: bool, family: IpType) -> Result<ChangeSet>;
 }
 
 #[async_trait(?Send)]
@@ -62,8 +130,30 @@ impl<P> DynProvider for P
 where
     P: Provider,
 {
-    async fn check_and_update(&self, new_ips: &[IpAddr], ttl: u32, force: bool, family: IpType) -> Result<Vec<IpAddr>> {
-        let mut real_used_ips = vec![];
+    fn domain(&self) -> Option<&str> {
+        Provider::domain(self)
+    }
+
+    async fn get_ips(&self, family: IpType) -> Result<Vec<IpAddr>> {
+        let dns_records = self.get_dns_record(family).await?;
+        Ok(dns_records.iter().map(|v| *v.as_ref()).collect())
+    }
+
+    async fn delete_ip(&self, ip: &IpAddr) -> Result<bool> {
+        let family = if ip.is_ipv4() { IpType::V4 } else { IpType::V6 };
+        let mut deleted = false;
+        for record in self.get_dns_record(family).await? {
+            if record.as_ref() == ip {
+                info!("delete dns record {}", ip);
+                self.delete_dns_record(&record).await?;
+                deleted = true;
+            }
+        }
+        Ok(deleted)
+    }
+
+    async fn check_and_update(&self, new_ips: &[IpAddr], ttl: u32, force: bool, family: IpType) -> Result<ChangeSet> {
+        let mut changes = vec![];
         let dns_records = self.get_dns_record(family).await?;
         if dns_records.is_empty() {
             info!("remote dns record(s) is empty");
@@ -98,31 +188,52 @@ where
                 let ip = item.ip;
                 info!("force updating dns record to {}", ip);
                 self.update_dns_record(record, ip).await?;
-                real_used_ips.push(*ip);
+                changes.push(Change {
+                    operation: Operation::ForceUpdate,
+                    old: Some(*ip),
+                    new: Some(*ip),
+                });
             }
         }
         while let (Some(old_item), Some(new_item)) = (olds.first(), news.first()) {
             let record = old_item.ref_record.unwrap();
+            let old_ip = old_item.ip;
             let new_ip = new_item.ip;
             olds.remove(0);
             news.remove(0);
             info!("updating dns record to {}", new_ip);
             self.update_dns_record(record, new_ip).await?;
-            real_used_ips.push(*new_ip);
+            changes.push(Change {
+                operation: Operation::Update,
+                old: Some(*old_ip),
+                new: Some(*new_ip),
+            });
         }
         for old_item in olds {
             info!("target ip {} not belong to this interface, delete it", old_item.ip);
             self.delete_dns_record(old_item.ref_record.unwrap()).await?;
+            changes.push(Change {
+                operation: Operation::Delete,
+                old: Some(*old_item.ip),
+                new: None,
+            });
         }
         for new_item in news {
             info!("target ip {} not exist in dns provider, create it", new_item.ip);
             self.create_dns_record(new_item.ip, ttl).await?;
-            real_used_ips.push(*new_item.ip);
+            changes.push(Change {
+                operation: Operation::Create,
+                old: None,
+                new: Some(*new_item.ip),
+            });
         }
-        if real_used_ips.is_empty() {
+        if changes.is_empty() {
             info!("remote and local are the same nothing to do");
         }
-        Ok(real_used_ips)
+        Ok(ChangeSet {
+            old_ips: dns_records.iter().map(|v| *v.as_ref()).collect(),
+            changes,
+        })
     }
 }
 
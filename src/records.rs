@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::factory::build_providers;
+use crate::providers::{record_type_from_ip, DynProvider};
+use crate::setting::Setting;
+use crate::{IpType, RecordsCommand, Shutdown};
+
+/// Manage the records of a configured provider directly, returns whether it succeeded
+pub(crate) async fn records(shutdown: Arc<Shutdown>, setting: Setting, command: RecordsCommand) -> bool {
+    let result = match command {
+        RecordsCommand::List { provider } => list(shutdown, setting, &provider).await,
+        RecordsCommand::Set { provider, ips } => set(shutdown, setting, &provider, &ips).await,
+        RecordsCommand::Delete { provider, ip } => delete(shutdown, setting, &provider, &ip).await,
+    };
+    match result {
+        Ok(()) => true,
+        Err(err) => {
+            println!("error: {err:#}");
+            false
+        },
+    }
+}
+
+/// Build only the named provider, returns it with its record name and ttl
+async fn build(
+    shutdown: Arc<Shutdown>,
+    setting: Setting,
+    name: &str,
+) -> Result<(Rc<Box<dyn DynProvider>>, String, u32)> {
+    let provider = setting
+        .providers
+        .get(name)
+        .ok_or_else(|| anyhow!("can't find provider '{}'", name))?
+        .clone();
+    let ttl = provider.ttl;
+    let mut providers = build_providers(shutdown, HashMap::from([(name.to_owned(), provider)])).await?;
+    let (provider, _, _) = providers.remove(name).unwrap();
+    let domain = provider.domain().unwrap_or(name).to_owned();
+    Ok((provider, domain, ttl))
+}
+
+async fn list(shutdown: Arc<Shutdown>, setting: Setting, name: &str) -> Result<()> {
+    let (provider, domain, _) = build(shutdown, setting, name).await?;
+    for family in [IpType::V4, IpType::V6] {
+        for ip in provider.get_ips(family).await? {
+            println!("{} {} {}", domain, record_type_from_ip(&ip), ip);
+        }
+    }
+    Ok(())
+}
+
+/// Make the records of each family of the addresses the same as them, the other family is left alone
+async fn set(shutdown: Arc<Shutdown>, setting: Setting, name: &str, ips: &[IpAddr]) -> Result<()> {
+    let (provider, domain, ttl) = build(shutdown, setting, name).await?;
+    for family in [IpType::V4, IpType::V6] {
+        let ips = ips
+            .iter()
+            .filter(|v| v.is_ipv4() == (family == IpType::V4))
+            .copied()
+            .collect::<Vec<_>>();
+        if ips.is_empty() {
+            continue;
+        }
+        let change_set = provider.check_and_update(&ips, ttl, false, family).await?;
+        if change_set.is_empty() {
+            println!("{} {} record(s) are up to date", domain, family);
+        }
+        for change in change_set.changes {
+            println!("{} {}", domain, change);
+        }
+    }
+    Ok(())
+}
+
+async fn delete(shutdown: Arc<Shutdown>, setting: Setting, name: &str, ip: &IpAddr) -> Result<()> {
+    let (provider, domain, _) = build(shutdown, setting, name).await?;
+    if !provider.delete_ip(ip).await? {
+        bail!("{} has no {} record of {}", domain, record_type_from_ip(ip), ip);
+    }
+    println!("{} delete {}", domain, ip);
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::env;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+const CREDENTIALS_DIRECTORY: &str = "CREDENTIALS_DIRECTORY";
+
+fn read_file<P: AsRef<Path>>(path: P) -> Result<String> {
+    let path = path.as_ref();
+    let content =
+        std::fs::read_to_string(path).map_err(|err| anyhow!("can't read secret file {}: {}", path.display(), err))?;
+    Ok(content.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+fn lookup(reference: &str) -> Result<String> {
+    let (scheme, name) = reference
+        .split_once(':')
+        .ok_or_else(|| anyhow!("secret reference ${{{}}} illegal", reference))?;
+    match scheme {
+        "env" => env::var(name).map_err(|_| anyhow!("environment variable {} is not set", name)),
+        "file" => read_file(name),
+        "credential" => {
+            if name.is_empty() || name.contains('/') {
+                bail!("credential name {} illegal", name);
+            }
+            let directory = env::var_os(CREDENTIALS_DIRECTORY)
+                .ok_or_else(|| anyhow!("{} is not set, credential {} isn't loaded", CREDENTIALS_DIRECTORY, name))?;
+            read_file(Path::new(&directory).join(name))
+        },
+        _ => bail!("unknown secret reference ${{{}}}", reference),
+    }
+}
+
+/// Replace the references to secrets in a string, so they don't need to be written in the config file
+///
+/// * `${env:NAME}`, the value of the environment variable
+/// * `${file:/path}`, the content of the file
+/// * `${credential:NAME}`, the credential passed by systemd `LoadCredential=`, read from `$CREDENTIALS_DIRECTORY`
+/// * `file:/path`, the whole value is the content of the file
+///
+/// References can be mixed with plain text, e.g. `Bearer ${env:TOKEN}`, `$${` is a literal `${`.
+/// The trailing newline of files is removed.
+fn resolve(value: &str) -> Result<String> {
+    if let Some(path) = value.strip_prefix("file:") {
+        return read_file(path);
+    }
+    let mut resolved = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        resolved.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(literal) = rest.strip_prefix("$${") {
+            resolved.push_str("${");
+            rest = literal;
+        } else if let Some(reference) = rest.strip_prefix("${") {
+            let end = reference
+                .find('}')
+                .ok_or_else(|| anyhow!("unclosed secret reference in {}", value))?;
+            resolved.push_str(&lookup(&reference[..end])?);
+            rest = &reference[end + 1..];
+        } else {
+            resolved.push('$');
+            rest = &rest[1..];
+        }
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+/// A credential arg of a provider or notifier, kept as written until it's resolved when building
+///
+/// Only the args of this type can refer to secrets, the others are used as they are written.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn resolve(&self) -> Result<String> {
+        resolve(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn resolve_plain() {
+        assert_eq!(resolve("plain $value").unwrap(), "plain $value");
+        assert_eq!(resolve("$").unwrap(), "$");
+    }
+
+    #[test]
+    fn resolve_escape() {
+        assert_eq!(resolve("$${env:HOME}").unwrap(), "${env:HOME}");
+        assert_eq!(resolve("a $${b} c").unwrap(), "a ${b} c");
+    }
+
+    #[test]
+    fn resolve_env() {
+        env::set_var("DDNS_RS_TEST_SECRET", "s3cret");
+        assert_eq!(resolve("Bearer ${env:DDNS_RS_TEST_SECRET}!").unwrap(), "Bearer s3cret!");
+        assert!(resolve("${env:DDNS_RS_TEST_MISSING}").is_err());
+    }
+
+    #[test]
+    fn resolve_unclosed() {
+        let err = resolve("Bearer ${env:TOKEN").unwrap_err();
+        assert!(err.to_string().contains("unclosed"), "{err}");
+    }
+
+    #[test]
+    fn resolve_unknown_scheme() {
+        let err = resolve("${vault:token}").unwrap_err();
+        assert!(err.to_string().contains("unknown secret reference"), "{err}");
+        let err = resolve("${token}").unwrap_err();
+        assert!(err.to_string().contains("illegal"), "{err}");
+    }
+
+    #[test]
+    fn resolve_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("token");
+        fs::write(&path, "s3cret\r\n\n").unwrap();
+        let path = path.display();
+        assert_eq!(resolve(&format!("file:{path}")).unwrap(), "s3cret");
+        assert_eq!(resolve(&format!("[${{file:{path}}}]")).unwrap(), "[s3cret]");
+        assert!(resolve("file:/nonexistent/ddns-rs/token").is_err());
+    }
+
+    #[test]
+    fn resolve_credential() {
+        // the other tests never set it
+        env::remove_var(CREDENTIALS_DIRECTORY);
+        let err = resolve("${credential:token}").unwrap_err();
+        assert!(err.to_string().contains(CREDENTIALS_DIRECTORY), "{err}");
+        assert!(resolve("${credential:../token}").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+/// The args of each kind of interface
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum InterfaceConfig {
+    Peer(PeerConfig),
+    Stock(StockConfig),
+    Static(StaticConfig),
+    File(FileConfig),
+    Composite(CompositeConfig),
+}
+
+impl InterfaceConfig {
+    /// Names of the other interfaces that must be built before this one
+    pub fn dependencies(&self) -> &[String] {
+        match self {
+            InterfaceConfig::Composite(config) => &config.members,
+            _ => &[],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PeerConfig {
+    pub url_v4: String,
+    pub url_v6: String,
+    pub ipv4_field_path: String,
+    pub ipv6_field_path: String,
+}
+
+/// Exactly one of `name`, `name_regex`, `mac` or `default_route` selects the interface
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StockConfig {
+    /// The name of the interface, `*` and `?` can be used as wildcards
+    pub name: Option<String>,
+    pub name_regex: Option<String>,
+    pub mac: Option<String>,
+    #[serde(default)]
+    pub default_route: bool,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub exclude_temporary: bool,
+    #[serde(default)]
+    pub exclude_deprecated: bool,
+    #[serde(default)]
+    pub prefer_stable: bool,
+    pub max_addresses: Option<usize>,
+    #[serde(default)]
+    pub allow_private: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StaticConfig {
+    pub addresses: Vec<IpAddr>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub path: String,
+    #[serde(default)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompositeStrategy {
+    #[default]
+    Fallback,
+    Union,
+    Consensus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CompositeConfig {
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub strategy: CompositeStrategy,
+    /// Used with the consensus strategy, the majority of the members by default
+    pub quorum: Option<usize>,
+}
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+pub use self::interface::*;
+pub use self::notifier::*;
+pub use self::provider::*;
+pub use self::watch::ConfigWatcher;
+use crate::event::EventKind;
+use crate::IpType;
+
+mod interface;
+mod notifier;
+mod provider;
+mod validate;
+mod watch;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Task {
+    pub family: String,
+    pub provider: String,
+    #[serde(default = "default_interval")]
+    pub interval: u32,
+    pub interface: String,
+    pub notifiers: Vec<String>,
+    /// Send `failed_repeatedly` when the task failed this many times in a row
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+fn default_interval() -> u32 {
+    60
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+impl Task {
+    /// The families of address updated by this task
+    pub fn families(&self) -> Result<&'static [IpType]> {
+        match &*self.family {
+            "ipv4" => Ok(&[IpType::V4]),
+            "ipv6" => Ok(&[IpType::V6]),
+            "all" => Ok(&[IpType::V4, IpType::V6]),
+            family => bail!("unknown family {}", family),
+        }
+    }
+}
+
+impl Default for Task {
+    fn default() -> Self {
+        Task {
+            interval: default_interval(),
+            interface: Default::default(),
+            family: Default::default(),
+            provider: Default::default(),
+            notifiers: Default::default(),
+            failure_threshold: default_failure_threshold(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Provider {
+    pub force: bool,
+    pub ttl: u32,
+    /// The kind and its args
+    #[serde(flatten)]
+    pub config: ProviderConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Interface {
+    #[serde(flatten)]
+    pub config: InterfaceConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Notifier {
+    /// The kinds of event will be sent by this notifier
+    #[serde(default = "default_events")]
+    pub events: Vec<EventKind>,
+    /// Drop an event equal to one sent within this many seconds, 0 disables it
+    #[serde(default)]
+    pub dedup_window: u64,
+    /// At most this many notifications in `rate_limit_period`, the rest are sent as a digest later, 0 is unlimited
+    #[serde(default)]
+    pub rate_limit: u32,
+    #[serde(default = "default_rate_limit_period")]
+    pub rate_limit_period: u64,
+    /// Collect the events and send them as a digest in every this many seconds, 0 disables it
+    #[serde(default)]
+    pub digest_interval: u64,
+    #[serde(flatten)]
+    pub config: NotifierConfig,
+}
+
+fn default_events() -> Vec<EventKind> {
+    vec![EventKind::Changed]
+}
+
+fn default_rate_limit_period() -> u64 {
+    3600
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Base {
+    #[serde(default = "default_task_startup_interval")]
+    pub task_startup_interval: u64,
+    #[serde(default = "default_task_retry_timeout")]
+    pub task_retry_timeout: u64,
+    /// Where the notifications waiting for delivery are kept, notifications are sent directly if not specified
+    #[serde(default)]
+    pub outbox_directory: Option<String>,
+    /// The delay before the first retry of a failed delivery, doubled on each retry
+    #[serde(default = "default_outbox_retry_interval")]
+    pub outbox_retry_interval: u64,
+    #[serde(default = "default_outbox_max_retry_interval")]
+    pub outbox_max_retry_interval: u64,
+    /// Notifications older than this many seconds are dropped, 0 keeps them forever
+    #[serde(default = "default_outbox_max_age")]
+    pub outbox_max_age: u64,
+}
+
+fn default_task_startup_interval() -> u64 {
+    5
+}
+
+fn default_task_retry_timeout() -> u64 {
+    10
+}
+
+fn default_outbox_retry_interval() -> u64 {
+    10
+}
+
+fn default_outbox_max_retry_interval() -> u64 {
+    3600
+}
+
+fn default_outbox_max_age() -> u64 {
+    86400
+}
+
+impl Default for Base {
+    fn default() -> Self {
+        Self {
+            task_startup_interval: default_task_startup_interval(),
+            task_retry_timeout: default_task_retry_timeout(),
+            outbox_directory: Default::default(),
+            outbox_retry_interval: default_outbox_retry_interval(),
+            outbox_max_retry_interval: default_outbox_max_retry_interval(),
+            outbox_max_age: default_outbox_max_age(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Setting {
+    #[serde(default)]
+    pub base: Base,
+    pub tasks: HashMap<String, Task>,
+    pub providers: HashMap<String, Provider>,
+    pub interfaces: HashMap<String, Interface>,
+    pub notifiers: HashMap<String, Notifier>,
+    /// The files, config directories and include patterns the config is loaded from, they are watched for changes
+    #[serde(skip)]
+    pub sources: Vec<PathBuf>,
+}
+
+/// The formats of the config file, they share the same schema
+#[derive(Debug, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum Format {
+    Toml,
+    Yaml,
+    Json,
+}
+
+impl Format {
+    /// Guess the format by the extension of the file, TOML if unknown
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Format {
+        let extension = path.as_ref().extension().map(|v| v.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("yaml") | Some("yml") => Format::Yaml,
+            Some("json") => Format::Json,
+            _ => Format::Toml,
+        }
+    }
+}
+
+/// The content of one config file, the main file or an included one
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Fragment {
+    base: Option<Base>,
+    /// Globs of the other files to merge, relative to the directory of this file
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    tasks: HashMap<String, Task>,
+    #[serde(default)]
+    providers: HashMap<String, Provider>,
+    #[serde(default)]
+    interfaces: HashMap<String, Interface>,
+    #[serde(default)]
+    notifiers: HashMap<String, Notifier>,
+}
+
+/// The extensions of the files merged from a config directory
+const CONFIG_EXTENSIONS: [&str; 4] = ["toml", "yaml", "yml", "json"];
+
+impl Setting {
+    /// The errors tell the path of the mistake, e.g. `notifiers.n1`, and the line in the file
+    fn parse<T: DeserializeOwned>(contents: &str, format: Format) -> Result<T> {
+        let with_path = |path: serde_path_to_error::Path, err: &dyn std::fmt::Display| match path.to_string() {
+            path if path == "." => anyhow!("{}", err),
+            path => anyhow!("{}: {}", path, err),
+        };
+        let value = match format {
+            Format::Toml => serde_path_to_error::deserialize(toml::Deserializer::new(contents))
+                .map_err(|err| with_path(err.path().clone(), err.inner()))?,
+            Format::Yaml => serde_path_to_error::deserialize(serde_yaml::Deserializer::from_str(contents))
+                .map_err(|err| with_path(err.path().clone(), err.inner()))?,
+            Format::Json => serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(contents))
+                .map_err(|err| with_path(err.path().clone(), err.inner()))?,
+        };
+        Ok(value)
+    }
+
+    /// Load the config from a file and the files it includes, or from all the config files in a directory
+    ///
+    /// The format of the main file can be specified, the others are detected by the extension. A name can only be
+    /// defined once across the files, and so can `base`.
+    pub fn load<P: AsRef<Path>>(path: P, format: Option<Format>) -> Result<Setting> {
+        let path = path.as_ref();
+        let mut loader = Loader::default();
+        if path.is_dir() {
+            loader.sources.push(path.to_owned());
+            for file in config_files(path)? {
+                loader.load(&file, None)?;
+            }
+        } else {
+            loader.load(path, format)?;
+        }
+        Ok(Setting {
+            base: loader.base.map(|(base, _)| base).unwrap_or_default(),
+            tasks: strip_origins(loader.tasks),
+            providers: strip_origins(loader.providers),
+            interfaces: strip_origins(loader.interfaces),
+            notifiers: strip_origins(loader.notifiers),
+            sources: loader.sources,
+        })
+    }
+
+    /// The named interfaces and the members they need, so only them are built
+    pub fn interfaces_with_members<'a>(&self, names: impl IntoIterator<Item = &'a str>) -> HashMap<String, Interface> {
+        let mut needed = HashMap::new();
+        let mut pending = names.into_iter().map(|v| v.to_owned()).collect::<Vec<_>>();
+        while let Some(next) = pending.pop() {
+            if let Some(interface) = self.interfaces.get(&next) {
+                if needed.insert(next, interface.clone()).is_none() {
+                    pending.extend_from_slice(interface.config.dependencies());
+                }
+            }
+        }
+        needed
+    }
+}
+
+/// The files with a config extension in the directory, in the order of their names, hidden files are skipped
+fn config_files(directory: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(directory)
+        .map_err(|err| anyhow!("can't read config directory {}: {}", directory.display(), err))?;
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_file() && is_config_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether the file is taken as a config file in a config directory, hidden files are not
+pub(super) fn is_config_file(path: &Path) -> bool {
+    let hidden = path.file_name().is_some_and(|v| v.to_string_lossy().starts_with('.'));
+    let known = path
+        .extension()
+        .is_some_and(|v| CONFIG_EXTENSIONS.contains(&&*v.to_string_lossy().to_lowercase()));
+    known && !hidden
+}
+
+fn strip_origins<T>(map: HashMap<String, (T, PathBuf)>) -> HashMap<String, T> {
+    map.into_iter().map(|(name, (value, _))| (name, value)).collect()
+}
+
+/// Merge the files into one config, remembering where each definition came from
+#[derive(Default)]
+struct Loader {
+    loaded: HashSet<PathBuf>,
+    sources: Vec<PathBuf>,
+    base: Option<(Base, PathBuf)>,
+    tasks: HashMap<String, (Task, PathBuf)>,
+    providers: HashMap<String, (Provider, PathBuf)>,
+    interfaces: HashMap<String, (Interface, PathBuf)>,
+    notifiers: HashMap<String, (Notifier, PathBuf)>,
+}
+
+impl Loader {
+    fn load(&mut self, path: &Path, format: Option<Format>) -> Result<()> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|err| anyhow!("can't read config {}: {}", path.display(), err))?;
+        if !self.loaded.insert(canonical) {
+            bail!("{} is included more than once", path.display());
+        }
+        self.sources.push(path.to_owned());
+        let contents =
+            fs::read_to_string(path).map_err(|err| anyhow!("can't read config {}: {}", path.display(), err))?;
+        let format = format.unwrap_or_else(|| Format::from_path(path));
+        let fragment: Fragment =
+            Setting::parse(&contents, format).map_err(|err| anyhow!("{}: {}", path.display(), err))?;
+
+        if let Some(base) = fragment.base {
+            if let Some((_, origin)) = &self.base {
+                bail!("base is defined in both {} and {}", origin.display(), path.display());
+            }
+            self.base = Some((base, path.to_owned()));
+        }
+        merge("task", path, fragment.tasks, &mut self.tasks)?;
+        merge("provider", path, fragment.providers, &mut self.providers)?;
+        merge("interface", path, fragment.interfaces, &mut self.interfaces)?;
+        merge("notifier", path, fragment.notifiers, &mut self.notifiers)?;
+
+        let directory = path.parent().unwrap_or_else(|| Path::new(""));
+        for pattern in fragment.include {
+            let full_pattern = directory.join(&pattern);
+            // the files matching it added later are noticed too
+            self.sources.push(full_pattern.clone());
+            let full_pattern = full_pattern.to_string_lossy();
+            let mut files = glob::glob(&full_pattern)
+                .map_err(|err| anyhow!("{}: illegal include '{}': {}", path.display(), pattern, err))?
+                .collect::<Result<Vec<_>, _>>()?;
+            // a glob matching nothing is fine, e.g. an empty conf.d, but a missing file is a mistake
+            if files.is_empty() && !pattern.contains(['*', '?', '[']) {
+                bail!("{}: can't find included file '{}'", path.display(), pattern);
+            }
+            files.sort();
+            for file in files {
+                self.load(&file, None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn merge<T>(kind: &str, path: &Path, from: HashMap<String, T>, into: &mut HashMap<String, (T, PathBuf)>) -> Result<()> {
+    for (name, value) in from {
+        if let Some((_, origin)) = into.get(&name) {
+            bail!(
+                "{} '{}' is defined in both {} and {}",
+                kind,
+                name,
+                origin.display(),
+                path.display()
+            );
+        }
+        into.insert(name, (value, path.to_owned()));
+    }
+    Ok(())
+}
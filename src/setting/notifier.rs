@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::notifiers::{EmailTls, TelegramParseMode};
+use crate::secret::Secret;
+
+/// The args of each kind of notifier
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum NotifierConfig {
+    Email(EmailConfig),
+    Webhook(WebhookConfig),
+    Telegram(TelegramConfig),
+    Slack(SlackConfig),
+    Discord(DiscordConfig),
+    Teams(TeamsConfig),
+    Ntfy(NtfyConfig),
+    Gotify(GotifyConfig),
+    Pushover(PushoverConfig),
+    DingTalk(DingTalkConfig),
+    #[serde(alias = "lark")]
+    Feishu(FeishuConfig),
+    WeCom(WeComConfig),
+    Bark(BarkConfig),
+    Mqtt(MqttConfig),
+    Exec(ExecConfig),
+    Empty(EmptyConfig),
+}
+
+/// A single value or a list of values
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value],
+            OneOrMany::Many(values) => values,
+        }
+    }
+}
+
+impl Default for OneOrMany {
+    fn default() -> Self {
+        OneOrMany::Many(vec![])
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    pub smtp_port: Option<u16>,
+    pub smtp_tls: Option<EmailTls>,
+    /// Kept for the configs written before `smtp_tls`, false is the same as implicit TLS
+    pub smtp_starttls: Option<bool>,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<Secret>,
+    pub subject: Option<String>,
+    pub html_template: Option<String>,
+    pub html_template_file: Option<String>,
+    pub text_template: Option<String>,
+    pub text_template_file: Option<String>,
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: OneOrMany,
+    #[serde(default)]
+    pub cc: OneOrMany,
+    #[serde(default)]
+    pub bcc: OneOrMany,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub method: Option<String>,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    pub content_type: Option<String>,
+    pub body: Option<String>,
+    pub authorization_header: Option<Secret>,
+    pub signing_secret: Option<Secret>,
+    pub signature_header: Option<String>,
+    pub timestamp_header: Option<String>,
+    pub delivery_id_header: Option<String>,
+    pub local_address: Option<IpAddr>,
+}
+
+/// A number or the username of a channel like `@channel`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TelegramChatId {
+    Id(i64),
+    Username(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub bot_token: Secret,
+    pub chat_id: TelegramChatId,
+    pub thread_id: Option<i64>,
+    pub parse_mode: Option<TelegramParseMode>,
+    #[serde(default)]
+    pub silent: bool,
+    pub api_base_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SlackConfig {
+    pub webhook_url: Secret,
+    pub channel: Option<String>,
+    pub username: Option<String>,
+    pub icon_emoji: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: Secret,
+    pub username: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TeamsConfig {
+    pub webhook_url: Secret,
+}
+
+/// A number from 1 to 5 or a name like `high`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum NtfyPriority {
+    Level(u8),
+    Name(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NtfyConfig {
+    pub server: Option<String>,
+    pub topic: String,
+    pub priority: Option<NtfyPriority>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub token: Option<Secret>,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GotifyConfig {
+    pub server: String,
+    pub app_token: Secret,
+    pub priority: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PushoverConfig {
+    pub user_key: Secret,
+    pub app_token: Secret,
+    pub device: Option<String>,
+    pub sound: Option<String>,
+    pub priority: Option<i8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DingTalkConfig {
+    pub webhook_url: Secret,
+    pub secret: Option<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FeishuConfig {
+    pub webhook_url: Secret,
+    pub secret: Option<Secret>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WeComConfig {
+    pub webhook_url: Secret,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BarkConfig {
+    pub server: Option<String>,
+    pub device_key: Secret,
+    pub group: Option<String>,
+    pub sound: Option<String>,
+    pub level: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MqttConfig {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(default)]
+    pub tls: bool,
+    pub client_id: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<Secret>,
+    pub topic: Option<String>,
+    #[serde(default = "default_mqtt_qos")]
+    pub qos: u8,
+    #[serde(default)]
+    pub retain: bool,
+    #[serde(default)]
+    pub home_assistant: bool,
+    pub discovery_prefix: Option<String>,
+}
+
+fn default_mqtt_qos() -> u8 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ExecConfig {
+    /// A program, or a program with its arguments
+    pub command: OneOrMany,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub working_directory: Option<String>,
+    #[serde(default)]
+    pub stdin: bool,
+    #[serde(default = "default_exec_timeout")]
+    pub timeout: u64,
+    #[serde(default = "default_exec_success_codes")]
+    pub success_codes: Vec<i32>,
+}
+
+fn default_exec_timeout() -> u64 {
+    30
+}
+
+fn default_exec_success_codes() -> Vec<i32> {
+    vec![0]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct EmptyConfig {}
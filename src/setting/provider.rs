@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use crate::secret::Secret;
+
+/// The args of each kind of provider
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ProviderConfig {
+    Cloudflare(CloudflareConfig),
+    Godaddy(GodaddyConfig),
+    Fake(FakeConfig),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CloudflareConfig {
+    pub token: Secret,
+    pub dns: String,
+    #[serde(default)]
+    pub proxied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GodaddyConfig {
+    pub api_key: Secret,
+    pub secret: Secret,
+    pub dns: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FakeConfig {
+    pub dns: Option<String>,
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use addr::parse_dns_name;
+use pnet::datalink::MacAddr;
+use pnet::ipnetwork::IpNetwork;
+use regex::Regex;
+
+use super::{InterfaceConfig, ProviderConfig, Setting};
+
+impl Setting {
+    /// Find the mistakes which are only noticed when the tasks are built or run, returns nothing if all is fine
+    ///
+    /// Nothing is resolved or connected here, secret references are left as they are.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        for (name, task) in &self.tasks {
+            if let Err(err) = task.families() {
+                problems.push(format!("tasks.{name}.family: {err}"));
+            }
+            if task.interval == 0 {
+                problems.push(format!("tasks.{name}.interval: must be greater than 0"));
+            }
+            if task.failure_threshold == 0 {
+                problems.push(format!("tasks.{name}.failure_threshold: must be greater than 0"));
+            }
+            if !self.providers.contains_key(&task.provider) {
+                problems.push(format!(
+                    "tasks.{name}.provider: can't find provider '{}'",
+                    task.provider
+                ));
+            }
+            if !self.interfaces.contains_key(&task.interface) {
+                problems.push(format!(
+                    "tasks.{name}.interface: can't find interface '{}'",
+                    task.interface
+                ));
+            }
+            for notifier in &task.notifiers {
+                if !self.notifiers.contains_key(notifier) {
+                    problems.push(format!("tasks.{name}.notifiers: can't find notifier '{notifier}'"));
+                }
+            }
+        }
+
+        for (name, provider) in &self.providers {
+            let dns = match &provider.config {
+                ProviderConfig::Cloudflare(config) => Some(&config.dns),
+                ProviderConfig::Godaddy(config) => Some(&config.dns),
+                ProviderConfig::Fake(config) => config.dns.as_ref(),
+            };
+            if let Some(dns) = dns {
+                match parse_dns_name(dns.trim_end_matches('.')) {
+                    Ok(v) if v.root().is_some() => {},
+                    Ok(_) => problems.push(format!(
+                        "providers.{name}.dns: '{dns}' isn't under a registrable domain"
+                    )),
+                    Err(err) => problems.push(format!(
+                        "providers.{name}.dns: '{dns}' isn't a valid record name: {err}"
+                    )),
+                }
+            }
+        }
+
+        for (name, interface) in &self.interfaces {
+            match &interface.config {
+                InterfaceConfig::Stock(config) => {
+                    let selectors = [
+                        config.name.is_some(),
+                        config.name_regex.is_some(),
+                        config.mac.is_some(),
+                        config.default_route,
+                    ];
+                    if selectors.iter().filter(|v| **v).count() != 1 {
+                        problems.push(format!(
+                            "interfaces.{name}: exactly one of name, name_regex, mac or default_route is required"
+                        ));
+                    }
+                    if let Some(Err(err)) = config.name_regex.as_ref().map(|v| Regex::new(v)) {
+                        problems.push(format!("interfaces.{name}.name_regex: {err}"));
+                    }
+                    if let Some(Err(err)) = config.mac.as_ref().map(|v| v.parse::<MacAddr>()) {
+                        problems.push(format!("interfaces.{name}.mac: {err:?}"));
+                    }
+                    for (field, networks) in [("include", &config.include), ("exclude", &config.exclude)] {
+                        for network in networks {
+                            if let Err(err) = network.parse::<IpNetwork>() {
+                                problems.push(format!("interfaces.{name}.{field}: '{network}': {err}"));
+                            }
+                        }
+                    }
+                },
+                InterfaceConfig::Composite(config) => {
+                    for member in &config.members {
+                        if !self.interfaces.contains_key(member) {
+                            problems.push(format!("interfaces.{name}.members: can't find interface '{member}'"));
+                        }
+                    }
+                    if config.members.is_empty() {
+                        problems.push(format!("interfaces.{name}.members: at least one member is required"));
+                    }
+                    if let Some(quorum) = config.quorum {
+                        if quorum == 0 || quorum > config.members.len() {
+                            problems.push(format!(
+                                "interfaces.{name}.quorum: must be between 1 and the number of members"
+                            ));
+                        }
+                    }
+                    if self.in_cycle(name) {
+                        problems.push(format!("interfaces.{name}.members: circular member reference"));
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        for (name, notifier) in &self.notifiers {
+            if notifier.rate_limit > 0 && notifier.rate_limit_period == 0 {
+                problems.push(format!(
+                    "notifiers.{name}.rate_limit_period: must be greater than 0 when rate_limit is set"
+                ));
+            }
+        }
+
+        problems.sort();
+        problems
+    }
+
+    /// Whether the interface can reach itself through the members of composite interfaces
+    fn in_cycle(&self, name: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = self
+            .interfaces
+            .get(name)
+            .map(|v| v.config.dependencies().to_vec())
+            .unwrap_or_default();
+        while let Some(next) = pending.pop() {
+            if next == name {
+                return true;
+            }
+            if visited.insert(next.clone()) {
+                if let Some(interface) = self.interfaces.get(&next) {
+                    pending.extend_from_slice(interface.config.dependencies());
+                }
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,115 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use glob::Pattern;
+use log::{debug, warn};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::Notify;
+
+use super::is_config_file;
+
+/// What the changed files are compared with, the paths are under the canonical watched directories
+enum Source {
+    /// A loaded file
+    File(PathBuf),
+    /// The config files of a config directory
+    Directory(PathBuf),
+    /// The files an include pattern matches
+    Pattern(Pattern),
+}
+
+impl Source {
+    fn matches(&self, path: &Path) -> bool {
+        match self {
+            Source::File(file) => path == file,
+            Source::Directory(directory) => path.parent() == Some(directory) && is_config_file(path),
+            Source::Pattern(pattern) => pattern.matches_path(path),
+        }
+    }
+}
+
+/// Notice the changes of the config files
+pub struct ConfigWatcher {
+    changed: Arc<Notify>,
+    // keep the watcher alive as long as this
+    _watcher: RecommendedWatcher,
+}
+
+/// The canonical directory holding the path
+fn parent_of(path: &Path) -> Result<PathBuf> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    parent
+        .canonicalize()
+        .map_err(|err| anyhow!("can't watch {}: {}", parent.display(), err))
+}
+
+impl ConfigWatcher {
+    /// Watch the directories holding the sources, so the files replaced by rename or added later are noticed too,
+    /// other files in them are ignored
+    pub fn create(sources: &[PathBuf]) -> Result<ConfigWatcher> {
+        let changed = Arc::new(Notify::new());
+        let mut directories = vec![];
+        let mut matched = vec![];
+        for source in sources {
+            let (directory, rule) = if source.is_dir() {
+                let directory = source
+                    .canonicalize()
+                    .map_err(|err| anyhow!("can't watch {}: {}", source.display(), err))?;
+                (directory.clone(), Source::Directory(directory))
+            } else if source.is_file() {
+                let directory = parent_of(source)?;
+                let file = directory.join(source.file_name().unwrap_or_default());
+                (directory, Source::File(file))
+            } else {
+                // an include pattern, only the ones in an existing directory can be watched
+                let Ok(directory) = parent_of(source) else {
+                    continue;
+                };
+                let file_name = source.file_name().unwrap_or_default().to_string_lossy();
+                let pattern = format!("{}/{}", Pattern::escape(&directory.to_string_lossy()), file_name);
+                let pattern =
+                    Pattern::new(&pattern).map_err(|err| anyhow!("can't watch {}: {}", source.display(), err))?;
+                (directory, Source::Pattern(pattern))
+            };
+            if !directories.contains(&directory) {
+                directories.push(directory);
+            }
+            matched.push(rule);
+        }
+        let mut watcher = notify::recommended_watcher(watch_handler(matched, changed.clone()))?;
+        for directory in &directories {
+            watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        }
+        Ok(ConfigWatcher {
+            changed,
+            _watcher: watcher,
+        })
+    }
+
+    pub async fn changed(&self) {
+        self.changed.notified().await;
+        debug!("config has been changed");
+    }
+}
+
+fn watch_handler(sources: Vec<Source>, changed: Arc<Notify>) -> impl Fn(notify::Result<notify::Event>) {
+    move |event| match event {
+        Ok(event) => {
+            if event.kind.is_access() {
+                return;
+            }
+            if event
+                .paths
+                .iter()
+                .any(|path| sources.iter().any(|source| source.matches(path)))
+            {
+                changed.notify_one();
+            }
+        },
+        Err(err) => warn!("watching config failed: {}", err),
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use anyhow::Result;
+use log::{debug, info};
+use tokio::task::{spawn_local, JoinHandle};
+use tokio::time::Duration;
+
+use crate::factory::{build_interfaces, build_notifiers, build_providers, ConfiguredProvider};
+use crate::interfaces::Interface;
+use crate::setting::Setting;
+use crate::{create_task, Shutdown, SubscribedNotifier};
+
+/// Keep the components built from the config and the tasks using them running
+///
+/// On reload only the components whose config changed are rebuilt, and only the tasks using them are restarted, the
+/// others keep their timers and state. The tasks are spawned on the local set, so it must run inside a `LocalSet`.
+pub(crate) struct Supervisor {
+    shutdown: Arc<Shutdown>,
+    /// The config applied at the moment
+    setting: Setting,
+    interfaces: HashMap<String, Rc<Box<dyn Interface>>>,
+    notifiers: HashMap<String, SubscribedNotifier>,
+    providers: HashMap<String, ConfiguredProvider>,
+    /// The background work of the notifiers
+    workers: HashMap<String, JoinHandle<()>>,
+    tasks: HashMap<String, JoinHandle<()>>,
+}
+
+/// The names whose config is new or different
+fn changed_names<T: PartialEq>(old: &HashMap<String, T>, new: &HashMap<String, T>) -> HashSet<String> {
+    new.iter()
+        .filter(|(name, value)| old.get(*name) != Some(*value))
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+impl Supervisor {
+    pub fn new(shutdown: Arc<Shutdown>) -> Supervisor {
+        Supervisor {
+            shutdown,
+            setting: Setting::default(),
+            interfaces: HashMap::new(),
+            notifiers: HashMap::new(),
+            providers: HashMap::new(),
+            workers: HashMap::new(),
+            tasks: HashMap::new(),
+        }
+    }
+
+    /// Switch to the validated config, everything keeps running as before if a component can't be built
+    pub async fn apply(&mut self, setting: Setting) -> Result<()> {
+        let old = &self.setting;
+
+        // composite interfaces are rebuilt with their members
+        let mut changed_interfaces = changed_names(&old.interfaces, &setting.interfaces);
+        loop {
+            let dependents = setting
+                .interfaces
+                .iter()
+                .filter(|(name, _)| !changed_interfaces.contains(*name))
+                .filter(|(_, v)| v.config.dependencies().iter().any(|v| changed_interfaces.contains(v)))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+            if dependents.is_empty() {
+                break;
+            }
+            changed_interfaces.extend(dependents);
+        }
+        // the outbox of every notifier is configured by base
+        let changed_notifiers = if old.base != setting.base {
+            setting.notifiers.keys().cloned().collect()
+        } else {
+            changed_names(&old.notifiers, &setting.notifiers)
+        };
+        let changed_providers = changed_names(&old.providers, &setting.providers);
+
+        // build everything before touching the running ones
+        let unchanged = self
+            .interfaces
+            .iter()
+            .filter(|(name, _)| setting.interfaces.contains_key(*name) && !changed_interfaces.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect();
+        let pending = setting
+            .interfaces
+            .iter()
+            .filter(|(name, _)| changed_interfaces.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect();
+        debug!("building {} interface(s)", changed_interfaces.len());
+        let interfaces = build_interfaces(pending, unchanged).await?;
+        let pending = setting
+            .notifiers
+            .iter()
+            .filter(|(name, _)| changed_notifiers.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect();
+        debug!("building {} notifier(s)", changed_notifiers.len());
+        let new_notifiers = build_notifiers(&setting.base, pending).await?;
+        let pending = setting
+            .providers
+            .iter()
+            .filter(|(name, _)| changed_providers.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect();
+        debug!("building {} provider(s)", changed_providers.len());
+        let new_providers = build_providers(self.shutdown.clone(), pending).await?;
+
+        let mut restarted = setting
+            .tasks
+            .iter()
+            .filter(|(name, task)| {
+                old.tasks.get(*name) != Some(*task)
+                    || changed_interfaces.contains(&task.interface)
+                    || changed_providers.contains(&task.provider)
+                    || task.notifiers.iter().any(|v| changed_notifiers.contains(v))
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+        restarted.sort();
+
+        // create the tasks with the components they will use, nothing is stopped if one of them can't be created
+        let mut notifiers = self
+            .notifiers
+            .iter()
+            .filter(|(name, _)| setting.notifiers.contains_key(*name) && !changed_notifiers.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect::<HashMap<_, _>>();
+        notifiers.extend(new_notifiers.iter().map(|(name, v)| (name.clone(), v.clone())));
+        let mut providers = self
+            .providers
+            .iter()
+            .filter(|(name, _)| setting.providers.contains_key(*name) && !changed_providers.contains(*name))
+            .map(|(name, v)| (name.clone(), v.clone()))
+            .collect::<HashMap<_, _>>();
+        providers.extend(new_providers);
+        let mut tasks = vec![];
+        for (i, name) in restarted.iter().enumerate() {
+            let task = create_task(
+                Duration::from_secs(setting.base.task_startup_interval * i as u64),
+                name.clone(),
+                &setting.tasks[name],
+                &interfaces,
+                &notifiers,
+                &providers,
+            )?;
+            tasks.push((name.clone(), task));
+        }
+
+        // stop the tasks and notifiers which are removed or replaced
+        self.tasks.retain(|name, task| {
+            let keep = setting.tasks.contains_key(name) && !restarted.contains(name);
+            if !keep {
+                task.abort();
+            }
+            keep
+        });
+        // let the replaced notifiers finish the delivery in progress, so the new ones don't send it again
+        for (name, (notifier, _)) in &self.notifiers {
+            if !setting.notifiers.contains_key(name) || changed_notifiers.contains(name) {
+                if let Some(notifier) = &**notifier {
+                    notifier.stop().await;
+                }
+            }
+        }
+        self.workers.retain(|name, worker| {
+            let keep = setting.notifiers.contains_key(name) && !changed_notifiers.contains(name);
+            if !keep {
+                worker.abort();
+            }
+            keep
+        });
+        self.interfaces = interfaces;
+        self.notifiers = notifiers;
+        self.providers = providers;
+        for (name, (notifier, _)) in new_notifiers {
+            self.workers.insert(
+                name,
+                spawn_local(async move {
+                    if let Some(notifier) = &*notifier {
+                        notifier.background().await;
+                    }
+                }),
+            );
+        }
+        for (name, task) in tasks {
+            self.tasks.insert(name, spawn_local(task));
+        }
+        info!(
+            "{} task(s) started, {} kept running",
+            restarted.len(),
+            self.tasks.len() - restarted.len()
+        );
+        self.setting = setting;
+        Ok(())
+    }
+
+    /// Stop all the tasks and notifiers
+    pub async fn stop(&mut self) {
+        for (_, task) in self.tasks.drain() {
+            task.abort();
+        }
+        for (notifier, _) in self.notifiers.values() {
+            if let Some(notifier) = &**notifier {
+                notifier.stop().await;
+            }
+        }
+        for (_, worker) in self.workers.drain() {
+            worker.abort();
+        }
+        self.interfaces.clear();
+        self.notifiers.clear();
+        self.providers.clear();
+        self.setting = Setting::default();
+    }
+}